@@ -0,0 +1,141 @@
+use crate::color::brightness;
+use crate::settings::{Adjustments, Levels};
+use image::RgbImage;
+
+/// Applies the adjustments to every pixel of an image.\
+/// Levels are stretched first, then brightness, contrast, gamma,
+/// saturation and invert are applied in that order.
+pub(crate) fn apply(img: &mut RgbImage, adjust: &Adjustments) {
+    if *adjust == Adjustments::default() {
+        return;
+    }
+
+    // New brightness for every brightness from 0 to 255
+    let levels = match adjust.levels {
+        Levels::None => None,
+        Levels::Auto => Some(auto_levels(img)),
+        Levels::Equalize => Some(equalize(img)),
+    };
+
+    for p in img.pixels_mut() {
+        let mut rgb = p.0.map(|c| c as f32);
+
+        if let Some(levels) = &levels {
+            // Scale the color so its brightness is the new one, which keeps the hue
+            let old = brightness(rgb);
+            let new = levels[old.round() as usize];
+            rgb = if old > 0. {
+                rgb.map(|c| c * new / old)
+            } else {
+                [new; 3]
+            };
+        }
+
+        rgb = rgb.map(|c| {
+            let c = c + adjust.brightness * 255.;
+            let c = (c - 127.5) * adjust.contrast + 127.5;
+            255. * (c.max(0.) / 255.).powf(1. / adjust.gamma)
+        });
+
+        let gray = brightness(rgb).clamp(0., 255.);
+        rgb = rgb.map(|c| gray + (c - gray) * adjust.saturation);
+
+        if adjust.invert {
+            rgb = rgb.map(|c| 255. - c);
+        }
+
+        p.0 = rgb.map(|c| c.round().clamp(0., 255.) as u8);
+    }
+}
+
+// Stretches brightness so the darkest pixels become black and the brightest white.
+// The darkest and brightest 0.5% are ignored so a few outliers don't stop the stretch
+fn auto_levels(img: &RgbImage) -> [f32; 256] {
+    let histogram = histogram(img);
+    let total: u32 = histogram.iter().sum();
+    let clip = total / 200;
+
+    let mut low = 0;
+    let mut count = 0;
+    while low < 255 && count + histogram[low] <= clip {
+        count += histogram[low];
+        low += 1;
+    }
+    let mut high = 255;
+    count = 0;
+    while high > low && count + histogram[high] <= clip {
+        count += histogram[high];
+        high -= 1;
+    }
+
+    let range = (high - low).max(1) as f32;
+    std::array::from_fn(|i| (i as f32 - low as f32) / range * 255.)
+}
+
+// Spreads brightness so every brightness is used by about the same number of pixels
+fn equalize(img: &RgbImage) -> [f32; 256] {
+    let histogram = histogram(img);
+    let total = histogram.iter().sum::<u32>().max(1) as f32;
+
+    let mut levels = [0.; 256];
+    let mut cumulative = 0;
+    for (level, count) in levels.iter_mut().zip(histogram) {
+        cumulative += count;
+        *level = cumulative as f32 / total * 255.;
+    }
+    levels
+}
+
+// Number of pixels with every brightness from 0 to 255
+fn histogram(img: &RgbImage) -> [u32; 256] {
+    let mut histogram = [0; 256];
+    for p in img.pixels() {
+        histogram[brightness(p.0.map(|c| c as f32)).round() as usize] += 1;
+    }
+    histogram
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    // One row of gray pixels from `from` to `to`
+    fn ramp(from: u8, to: u8) -> RgbImage {
+        RgbImage::from_fn((to - from) as u32 + 1, 1, |x, _| Rgb([from + x as u8; 3]))
+    }
+
+    #[test]
+    fn default_changes_nothing() {
+        let original = RgbImage::from_fn(16, 16, |x, y| Rgb([x as u8 * 16, y as u8 * 16, 77]));
+        let mut img = original.clone();
+        apply(&mut img, &Adjustments::default());
+        assert_eq!(img, original);
+    }
+
+    #[test]
+    fn invert() {
+        let mut img = RgbImage::from_pixel(2, 2, Rgb([0, 100, 255]));
+        let adjust = Adjustments {
+            invert: true,
+            ..Default::default()
+        };
+        apply(&mut img, &adjust);
+        assert!(img.pixels().all(|p| p.0 == [255, 155, 0]));
+    }
+
+    #[test]
+    fn auto_levels_stretch_to_black_and_white() {
+        let mut img = ramp(100, 163);
+        let adjust = Adjustments {
+            levels: Levels::Auto,
+            ..Default::default()
+        };
+        apply(&mut img, &adjust);
+        assert_eq!(img.get_pixel(0, 0).0, [0; 3]);
+        assert_eq!(img.get_pixel(63, 0).0, [255; 3]);
+        // Stays in order
+        let values: Vec<u8> = img.pixels().map(|p| p.0[0]).collect();
+        assert!(values.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
@@ -1,6 +1,10 @@
-use crate::video::AsciiVideo;
+use crate::video::{AsciiVideo, BufferedFrames, Clock, PlaybackStats, WallClock};
 use anyhow::bail;
-use rodio::Decoder;
+use rodio::{Decoder, OutputStream, Sample, Sink, Source};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use std::{
     io::{BufReader, Cursor},
     process::Command,
@@ -10,7 +14,7 @@ impl AsciiVideo {
     /// Separates audio from video and encodes it in mp3.
     pub fn ffmpeg_audio(&mut self, path: &str) -> anyhow::Result<()> {
         let cmd = Command::new("ffmpeg")
-            .args(["-i", &path])
+            .args(["-i", path])
             .arg("-vn")
             .args(["-f", "mp3"])
             .arg("-")
@@ -22,23 +26,168 @@ impl AsciiVideo {
             bail!("ffmpeg stderr: {}", String::from_utf8_lossy(&cmd.stderr))
         }
     }
-    /// Plays video in terminal with audio
-    pub fn play_with_audio(mut self) -> anyhow::Result<()> {
-        if self.audio.is_none() {
-            bail!("Can't play video in terminal. `audio` is None")
-        }
+    /// Plays video in terminal with audio.\
+    /// The video follows the audio, frames are shown or skipped to match the audio position.
+    pub fn play_with_audio(mut self) -> anyhow::Result<PlaybackStats> {
         if self.ascii_frames.is_none() {
             bail!("`ascii_frames` is empty. Can't play video in terminal.")
         }
-        let (_stream, handle) = rodio::OutputStream::try_default()?;
-        let sink = rodio::Sink::try_new(&handle)?;
+        let mut clock = self.start_audio()?;
+        let frames = BufferedFrames {
+            frames: self.ascii_frames.take().unwrap(),
+            index: 0,
+        };
+        self.play_frames(frames, &mut clock)
+    }
+    /// Plays the video at `path` in terminal with audio while it is being decoded.\
+    /// See `play_stream` and `play_with_audio`.
+    pub fn play_stream_with_audio(mut self, path: &str) -> anyhow::Result<PlaybackStats> {
+        let frames = self.stream(path)?;
+        let mut clock = self.start_audio()?;
+        self.play_frames(frames, &mut clock)
+    }
+    /// Starts playing `audio`.\
+    /// Audio stops when the returned clock is dropped.
+    fn start_audio(&mut self) -> anyhow::Result<AudioClock> {
+        if self.audio.is_none() {
+            bail!("Can't play video in terminal. `audio` is None")
+        }
+        let (stream, handle) = OutputStream::try_default()?;
+        let mut clock = AudioClock {
+            _stream: stream,
+            sink: Sink::try_new(&handle)?,
+            audio: self.audio.take().unwrap().into(),
+            samples: Arc::new(AtomicU64::new(0)),
+            rate: 0,
+            end: Cell::new(None),
+        };
+        clock.queue(Duration::ZERO)?;
+
+        clock.sink.play();
+        Ok(clock)
+    }
+}
+
+// Follows the position of the playing audio
+struct AudioClock {
+    _stream: OutputStream,
+    sink: Sink,
+    // mp3 encoded, decoded again when seeking
+    audio: Arc<[u8]>,
+    // Samples played so far
+    samples: Arc<AtomicU64>,
+    // Samples per second of audio
+    rate: u64,
+    // Takes over once the audio has ended
+    end: Cell<Option<WallClock>>,
+}
 
+impl AudioClock {
+    // Adds the audio starting from `position` to the sink
+    fn queue(&mut self, position: Duration) -> anyhow::Result<()> {
         // Decode audio file
-        let decoder = Decoder::new(BufReader::new(Cursor::new(self.audio.take().unwrap())))?;
-        sink.append(decoder);
+        let decoder = Decoder::new(BufReader::new(Cursor::new(self.audio.clone())))?;
+        self.rate = decoder.sample_rate() as u64 * decoder.channels() as u64;
+        self.samples.store(
+            (position.as_secs_f64() * self.rate as f64) as u64,
+            Ordering::Relaxed,
+        );
+        self.sink.append(Tracked {
+            inner: decoder.skip_duration(position),
+            samples: self.samples.clone(),
+        });
+        Ok(())
+    }
+    // Changes the clock used after the audio has ended, if there is one yet
+    fn update_end(&self, update: impl FnOnce(&mut WallClock)) {
+        if let Some(mut end) = self.end.get() {
+            update(&mut end);
+            self.end.set(Some(end));
+        }
+    }
+}
 
-        sink.play();
-        self.play()?;
+impl Clock for AudioClock {
+    fn position(&self) -> Duration {
+        // If the video is longer than the audio continue in real time after the audio ends
+        if let Some(end) = self.end.get() {
+            return end.position();
+        }
+        let position =
+            Duration::from_secs_f64(self.samples.load(Ordering::Relaxed) as f64 / self.rate as f64);
+        if self.sink.empty() {
+            let mut end = WallClock::starting_at(position);
+            end.set_speed(self.sink.speed());
+            if self.sink.is_paused() {
+                end.pause();
+            }
+            self.end.set(Some(end));
+        }
+        position
+    }
+    fn speed(&self) -> f32 {
+        self.sink.speed()
+    }
+    fn set_speed(&mut self, speed: f32) {
+        self.sink.set_speed(speed);
+        self.update_end(|end| end.set_speed(speed));
+    }
+    fn pause(&mut self) {
+        self.sink.pause();
+        self.update_end(|end| end.pause());
+    }
+    fn resume(&mut self) {
+        self.sink.play();
+        self.update_end(|end| end.resume());
+    }
+    fn seek(&mut self, position: Duration) -> anyhow::Result<()> {
+        let paused = self.sink.is_paused();
+        // Also pauses the sink
+        self.sink.clear();
+        self.end.set(None);
+        self.queue(position)?;
+        if !paused {
+            self.sink.play();
+        }
         Ok(())
     }
 }
+
+// Audio source that counts the samples taken from it
+struct Tracked<S> {
+    inner: S,
+    samples: Arc<AtomicU64>,
+}
+
+impl<S: Source> Iterator for Tracked<S>
+where
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let sample = self.inner.next();
+        if sample.is_some() {
+            self.samples.fetch_add(1, Ordering::Relaxed);
+        }
+        sample
+    }
+}
+
+impl<S: Source> Source for Tracked<S>
+where
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
@@ -3,7 +3,10 @@
 //! Also a good example on how to use paxcii library side.
 
 use clap::{value_parser, Arg, ArgAction, ArgMatches, Command as ClapCommand};
+use image::ImageFormat;
 use paxcii::*;
+use std::io::{stdin, stdout, IsTerminal};
+use std::time::Duration;
 
 // Bold and red 'error: '
 const ERR_MSG: &str = "\x1b[31;1merror\x1b[0m: ";
@@ -14,35 +17,129 @@ fn main() -> anyhow::Result<()> {
     let args = process_args(cmd);
 
     if args.video {
-        eprint!("Opening video...");
-        let mut ascii_video = AsciiVideo::open(&args.path, args.settings)?;
-        eprintln!("Done");
+        if matches!(
+            args.format,
+            OutputFormat::Text | OutputFormat::Svg | OutputFormat::Png | OutputFormat::Jpeg
+        ) {
+            anyhow::bail!("Text, svg, png and jpeg formats aren't supported for video");
+        }
+        if args.format != OutputFormat::Ansi && args.output_file.is_none() {
+            anyhow::bail!("Html, gif and video formats for video need an output file");
+        }
+        if args.audio && args.output_file.is_some() && args.format != OutputFormat::Video {
+            anyhow::bail!("Audio can only be added to output in video format");
+        }
         if let Some(output_file) = args.output_file {
-            ascii_video.write_bash_script(&output_file)?;
-        } else {
+            eprint!("Opening video...");
+            let mut ascii_video = AsciiVideo::new(args.settings);
+            ascii_video.ffmpeg(&args.path)?;
+            if let Some(threads) = args.threads {
+                ascii_video.video_to_ascii_parallel(threads)?;
+            } else {
+                ascii_video.video_to_ascii()?;
+            }
+            eprintln!("Done");
             if args.audio {
                 eprint!("Extracting audio...");
                 ascii_video.ffmpeg_audio(&args.path)?;
                 eprintln!("Done");
-                ascii_video.play_with_audio()?;
-            } else {
-                ascii_video.play()?;
             }
+            match args.format {
+                OutputFormat::Html => ascii_video.write_html(&output_file)?,
+                OutputFormat::Gif => {
+                    ascii_video.write_gif(&output_file, &args.raster, &args.gif)?
+                }
+                OutputFormat::Video => {
+                    eprint!("Encoding video...");
+                    ascii_video.write_video(&output_file, &args.raster)?;
+                    eprintln!("Done");
+                }
+                _ => ascii_video.write_bash_script(&output_file)?,
+            }
+        } else {
+            // Frames are converted while the video plays
+            let mut ascii_video = AsciiVideo::new(args.settings);
+            ascii_video.playback = args.playback;
+            let stats = if args.audio {
+                eprint!("Extracting audio...");
+                ascii_video.ffmpeg_audio(&args.path)?;
+                eprintln!("Done");
+                ascii_video.play_stream_with_audio(&args.path)?
+            } else {
+                ascii_video.play_stream(&args.path)?
+            };
+            eprintln!("{stats}");
         }
     } else if let Some(index) = args.webcam {
         webcam(index, &args.settings)?;
     } else {
         let ascii_img = AsciiImage::open(&args.path, args.settings)?;
-        if let Some(output_file) = args.output_file {
-            ascii_img.write(&output_file)?;
-        } else {
-            ascii_img.print();
+        match (args.format, args.output_file) {
+            (OutputFormat::Ansi, Some(output_file)) => ascii_img.write(&output_file)?,
+            (OutputFormat::Ansi, None) => ascii_img.print(),
+            (OutputFormat::Text, Some(output_file)) => {
+                ascii_img.write_text(&output_file, &args.text)?
+            }
+            (OutputFormat::Text, None) => {
+                if let Some(ascii) = &ascii_img.ascii {
+                    print!("{}", ascii.to_plain(&args.text));
+                }
+            }
+            (OutputFormat::Html, Some(output_file)) => ascii_img.write_html(&output_file)?,
+            (OutputFormat::Html, None) => {
+                if let Some(ascii) = &ascii_img.ascii {
+                    println!("{}", ascii.to_html(ascii_img.settings.color));
+                }
+            }
+            (OutputFormat::Svg, Some(output_file)) => {
+                ascii_img.write_svg(&output_file, &args.svg)?
+            }
+            (OutputFormat::Svg, None) => {
+                if let Some(ascii) = &ascii_img.ascii {
+                    print!("{}", ascii.to_svg(ascii_img.settings.color, &args.svg));
+                }
+            }
+            (OutputFormat::Png | OutputFormat::Jpeg, Some(output_file)) => {
+                let format = if args.format == OutputFormat::Png {
+                    ImageFormat::Png
+                } else {
+                    ImageFormat::Jpeg
+                };
+                ascii_img.write_image(&output_file, format, &args.raster)?
+            }
+            (OutputFormat::Png | OutputFormat::Jpeg, None) => {
+                anyhow::bail!("Png and jpeg formats need an output file")
+            }
+            (OutputFormat::Gif | OutputFormat::Video, _) => {
+                anyhow::bail!("Gif and video formats are only supported for video")
+            }
         }
     }
 
     Ok(())
 }
 
+// How the ascii is written
+#[derive(Default, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    // Text with ansi escape sequences for colors
+    #[default]
+    Ansi,
+    // Only the characters
+    Text,
+    // Html page
+    Html,
+    // Svg image
+    Svg,
+    // Images drawn with the built-in font
+    Png,
+    Jpeg,
+    // Animated gif drawn with the built-in font
+    Gif,
+    // Video file encoded by ffmpeg, drawn with the built-in font
+    Video,
+}
+
 // Result of `process_args`
 #[derive(Default)]
 struct ProcessedArgs {
@@ -51,7 +148,14 @@ struct ProcessedArgs {
     video: bool,
     audio: bool,
     output_file: Option<String>,
+    format: OutputFormat,
+    text: TextSettings,
+    svg: SvgSettings,
+    raster: RasterSettings,
+    gif: GifSettings,
     webcam: Option<u32>,
+    threads: Option<usize>,
+    playback: PlaybackSettings,
 }
 
 fn process_args(cmd: ArgMatches) -> ProcessedArgs {
@@ -70,6 +174,41 @@ fn process_args(cmd: ArgMatches) -> ProcessedArgs {
         args.output_file = Some(x.clone());
     }
 
+    // Get output format
+    if let Some(x) = cmd.get_one::<String>("format") {
+        args.format = match x.as_str() {
+            "text" => OutputFormat::Text,
+            "html" => OutputFormat::Html,
+            "svg" => OutputFormat::Svg,
+            "png" => OutputFormat::Png,
+            "jpeg" => OutputFormat::Jpeg,
+            "gif" => OutputFormat::Gif,
+            "video" => OutputFormat::Video,
+            _ => OutputFormat::Ansi,
+        };
+    }
+    if cmd.get_flag("trim") {
+        args.text.trim = true;
+    }
+    if let Some(x) = cmd.get_one::<String>("line-ending") {
+        args.text.line_ending = match x.as_str() {
+            "crlf" => LineEnding::CrLf,
+            _ => LineEnding::Lf,
+        };
+    }
+    if let Some(x) = cmd.get_one::<String>("font-family") {
+        args.svg.font_family = x.clone();
+    }
+    if let Some(x) = cmd.get_one::<f32>("font-size") {
+        args.svg.font_size = *x;
+    }
+    if let Some(x) = cmd.get_one::<u16>("gif-repeat") {
+        args.gif.repeat = Some(*x);
+    }
+    if let Some(x) = cmd.get_one::<u16>("palette-size") {
+        args.gif.palette_size = Some(*x);
+    }
+
     if cmd.get_flag("audio") {
         args.audio = true;
     }
@@ -79,11 +218,104 @@ fn process_args(cmd: ArgMatches) -> ProcessedArgs {
         args.webcam = Some(*x);
     }
 
+    // Get number of threads for converting video
+    if let Some(x) = cmd.get_one::<usize>("threads") {
+        args.threads = Some(*x);
+    }
+
+    if cmd.get_flag("delta") {
+        args.playback.delta = true;
+    }
+
+    // Playback can only be controlled with keys from a terminal
+    args.playback.controls = stdin().is_terminal();
+
+    // Get audio/video sync tolerance
+    if let Some(x) = cmd.get_one::<u64>("sync-tolerance") {
+        args.playback.sync_tolerance = Duration::from_millis(*x);
+    }
+
     if cmd.get_flag("no-color") {
-        args.settings.color = false;
+        args.settings.color = ColorDepth::None;
         args.settings.chars_light();
     }
 
+    // Get color depth
+    if let Some(x) = cmd.get_one::<String>("color-depth") {
+        args.settings.color = match x.as_str() {
+            "none" => ColorDepth::None,
+            "16" => ColorDepth::Ansi16,
+            "256" => ColorDepth::Ansi256,
+            _ => ColorDepth::TrueColor,
+        };
+        if args.settings.color == ColorDepth::None {
+            args.settings.chars_light();
+        }
+    }
+
+    // Get background color mode
+    if let Some(x) = cmd.get_one::<String>("background") {
+        args.settings.background = match x.as_str() {
+            "pixel" => Background::Pixel,
+            "darkened" => Background::Darkened,
+            _ => Background::None,
+        };
+    }
+
+    // Get render mode
+    if let Some(x) = cmd.get_one::<String>("mode") {
+        args.settings.mode = match x.as_str() {
+            "half-block" => RenderMode::HalfBlock,
+            "braille" => RenderMode::Braille,
+            "structural" => RenderMode::Structural,
+            "glyph" => RenderMode::Glyph,
+            _ => RenderMode::Chars,
+        };
+    }
+
+    // Get how glyphs are matched
+    if let Some(x) = cmd.get_one::<String>("glyph-metric") {
+        args.settings.glyph_metric = match x.as_str() {
+            "ssim" => GlyphMetric::Ssim,
+            _ => GlyphMetric::Error,
+        };
+    }
+
+    // Get dithering method
+    if let Some(x) = cmd.get_one::<String>("dither") {
+        args.settings.dither = match x.as_str() {
+            "floyd-steinberg" => Dither::FloydSteinberg,
+            "atkinson" => Dither::Atkinson,
+            "bayer4" => Dither::Bayer4,
+            "bayer8" => Dither::Bayer8,
+            _ => Dither::None,
+        };
+    }
+
+    // Get image adjustments
+    if let Some(x) = cmd.get_one::<f32>("brightness") {
+        args.settings.adjust.brightness = *x;
+    }
+    if let Some(x) = cmd.get_one::<f32>("contrast") {
+        args.settings.adjust.contrast = *x;
+    }
+    if let Some(x) = cmd.get_one::<f32>("gamma") {
+        args.settings.adjust.gamma = *x;
+    }
+    if let Some(x) = cmd.get_one::<f32>("saturation") {
+        args.settings.adjust.saturation = *x;
+    }
+    if cmd.get_flag("invert") {
+        args.settings.adjust.invert = true;
+    }
+    if let Some(x) = cmd.get_one::<String>("levels") {
+        args.settings.adjust.levels = match x.as_str() {
+            "auto" => Levels::Auto,
+            "equalize" => Levels::Equalize,
+            _ => Levels::None,
+        };
+    }
+
     if cmd.get_flag("no-preserve-aspect-ratio") {
         args.settings.keep_aspect_ratio = false;
     }
@@ -96,9 +328,17 @@ fn process_args(cmd: ArgMatches) -> ProcessedArgs {
             "light" => args.settings.chars_light(),
             "medium" => args.settings.chars_medium(),
             "filled" => args.settings.chars_filled(),
+            "ascii" => args.settings.chars_ascii(),
+            "braille" => args.settings.mode = RenderMode::Braille,
+            // Any other value is used as the characters themselves
             _ => {
-                eprintln!("{}Invalid value for argument 'char-set'. Value can only be: light/medium/filled. medium will be used", ERR_MSG);
-                args.settings.chars_medium()
+                if let Err(e) = args.settings.chars_custom(&c, cmd.get_flag("sort-chars")) {
+                    eprintln!(
+                        "{}Invalid value for argument 'char-set'. {}. medium will be used",
+                        ERR_MSG, e
+                    );
+                    args.settings.chars_medium()
+                }
             }
         }
     }
@@ -108,6 +348,15 @@ fn process_args(cmd: ArgMatches) -> ProcessedArgs {
         (args.settings.width, args.settings.height) = s;
     }
 
+    // Get cell aspect ratio from the user, or from the terminal if the ascii is shown in it
+    if let Some(x) = cmd.get_one::<f32>("cell-aspect") {
+        args.settings.cell_aspect = *x;
+    } else if args.output_file.is_none() && stdout().is_terminal() {
+        if let Some(x) = get_cell_aspect() {
+            args.settings.cell_aspect = x;
+        }
+    }
+
     if let Some(w) = cmd.get_one::<u32>("width") {
         args.settings.width = *w;
     }
@@ -115,6 +364,13 @@ fn process_args(cmd: ArgMatches) -> ProcessedArgs {
         args.settings.height = *h;
     }
 
+    // Size of characters in png, jpeg, gif and video output, as tall as the cells the ascii is made for
+    if let Some(x) = cmd.get_one::<u32>("cell-width") {
+        args.raster.cell_width = *x;
+    }
+    args.raster.cell_height =
+        (args.raster.cell_width as f32 * args.settings.cell_aspect).round() as u32;
+
     args
 }
 
@@ -127,6 +383,14 @@ fn get_term_size() -> Option<(u32, u32)> {
     }
 }
 
+// Terminals that don't tell their cell size are assumed to have the default cell aspect ratio
+fn get_cell_aspect() -> Option<f32> {
+    #[cfg(unix)]
+    return term::cell_aspect();
+    #[cfg(not(unix))]
+    return None;
+}
+
 // Creates the cli interface
 fn create_cli() -> ArgMatches {
     let cmd = ClapCommand::new("paxcii")
@@ -147,7 +411,10 @@ fn create_cli() -> ArgMatches {
             Arg::new("video")
                 .short('v')
                 .long("video")
-                .help("Path to input video file")
+                .help("\
+                    Path to input video file. \
+                    While playing, space pauses, left and right arrows seek 5 seconds, \
+                    +/- change speed and q quits")
                 .value_name("example.mp4")
                 .num_args(1)
                 .action(ArgAction::Set)
@@ -158,20 +425,140 @@ fn create_cli() -> ArgMatches {
                 .long("output-file")
                 .help("\
                     Print ascii output to file with specified path instead of stdout. \
-                    For image the ascii is written as is into the file. \
-                    For video a bash script is created that plays the video when executed")
+                    For image the ascii is written into the file in the format of --format. \
+                    For video a bash script is created that plays the video when executed, \
+                    or a web page with --format html")
                 .value_name("example.sh")
                 .num_args(1)
                 .action(ArgAction::Set)
         )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("\
+                    Format of the output. ansi(default) uses escape sequences for colors. \
+                    text is only the characters, for pasting into documents and editors. \
+                    html is a web page, without output-file only its <pre> element is printed. \
+                    For video html plays the video in a browser and needs output-file. \
+                    svg is a scalable image, only for images. \
+                    png and jpeg draw the characters with a built-in font, only for images \
+                    and they need output-file. \
+                    gif is an animation drawn with the built-in font, only for video \
+                    and it needs output-file. \
+                    video is encoded by ffmpeg into a file like mp4 or webm, picked from the \
+                    extension of output-file, and drawn with the built-in font. Only for video. \
+                    Options: ansi/text/html/svg/png/jpeg/gif/video")
+                .value_name("ansi")
+                .num_args(1)
+                .value_parser(["ansi", "text", "html", "svg", "png", "jpeg", "gif", "video"])
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("trim")
+                .long("trim")
+                .help("Remove whitespace at the end of lines of text output")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("line-ending")
+                .long("line-ending")
+                .help("Line ending of text output. Options: lf(default)/crlf")
+                .value_name("lf")
+                .num_args(1)
+                .value_parser(["lf", "crlf"])
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("font-family")
+                .long("font-family")
+                .help("Font of svg output, should be monospace")
+                .value_name("monospace")
+                .num_args(1)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("font-size")
+                .long("font-size")
+                .help("Font size of svg output in pixels")
+                .value_name("14")
+                .num_args(1)
+                .value_parser(value_parser!(f32))
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("cell-width")
+                .long("cell-width")
+                .help("\
+                    Width of characters in png, jpeg, gif and video output in pixels. \
+                    Their height follows from the cell aspect ratio")
+                .value_name("8")
+                .num_args(1)
+                .value_parser(value_parser!(u32))
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("gif-repeat")
+                .long("gif-repeat")
+                .help("How many times gif output plays again after the first time. Repeats forever by default")
+                .num_args(1)
+                .value_parser(value_parser!(u16))
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("palette-size")
+                .long("palette-size")
+                .help("Most colors every frame of gif output can use, from 2 to 256")
+                .value_name("256")
+                .num_args(1)
+                .value_parser(value_parser!(u16).range(2..=256))
+                .action(ArgAction::Set)
+        )
         .arg(
             Arg::new("audio")
                 .short('a')
                 .long("audio")
-                .help("Separates audio from video using ffmpeg and then plays the audio at the same time as the video.")
+                .help("\
+                    Separates audio from video using ffmpeg and then plays the audio at the same time as the video. \
+                    With --format video the audio is added to the output file instead.")
+                .conflicts_with_all(["image", "webcam"])
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("threads")
+                .short('t')
+                .long("threads")
+                .help("\
+                    Convert video frames on multiple threads. 0 uses one thread per cpu core. \
+                    Only used when writing video to a file with output-file")
+                .value_name("0")
+                .num_args(1)
+                .value_parser(value_parser!(usize))
+                .requires("output-file")
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("delta")
+                .short('d')
+                .long("delta")
+                .help("\
+                    Only redraw characters that changed since the previous video frame. \
+                    Makes videos with static parts much faster to print")
                 .conflicts_with_all(["image", "webcam", "output-file"])
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new("sync-tolerance")
+                .short('s')
+                .long("sync-tolerance")
+                .help("\
+                    How many milliseconds a video frame can be behind the audio, \
+                    or behind real time without audio, before frames are skipped")
+                .value_name("40")
+                .num_args(1)
+                .value_parser(value_parser!(u64))
+                .conflicts_with_all(["image", "webcam", "output-file"])
+                .action(ArgAction::Set)
+        )
         .arg(
             Arg::new("webcam")
             .short('e')
@@ -189,20 +576,151 @@ fn create_cli() -> ArgMatches {
                 .help("Prints image without colors")
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new("color-depth")
+                .long("color-depth")
+                .help("\
+                    Number of colors the terminal can show. \
+                    Options: none/16/256/truecolor(default)")
+                .value_name("truecolor")
+                .num_args(1)
+                .value_parser(["none", "16", "256", "truecolor"])
+                .conflicts_with("no-color")
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("background")
+                .short('b')
+                .long("background")
+                .help("\
+                    Also color the background of characters. \
+                    pixel uses the pixel color as background with a contrasting character on top. \
+                    darkened uses a darkened pixel color as background. \
+                    Options: none(default)/pixel/darkened")
+                .value_name("none")
+                .num_args(1)
+                .value_parser(["none", "pixel", "darkened"])
+                .conflicts_with("no-color")
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("mode")
+                .short('m')
+                .long("mode")
+                .help("\
+                    How pixels are drawn. chars(default) draws characters from char-set. \
+                    half-block draws two pixels per character for double resolution. \
+                    braille draws 2x4 pixels per character as braille dots. \
+                    structural is like chars but draws edges as lines with |/\\-_. \
+                    glyph draws the character from char-set whose shape matches the pixels best, \
+                    use it with --char-set ascii")
+                .value_name("chars")
+                .num_args(1)
+                .value_parser(["chars", "half-block", "braille", "structural", "glyph"])
+                .action(ArgAction::Set)
+        )
         .arg(
             Arg::new("char-set")
                 .short('c')
                 .long("char-set")
-                .help("Choose character set to use for result. Options: light(default with no color)/medium(default)/filled")
+                .help("\
+                    Choose character set to use for result. Options: light(default with no color)/medium(default)/filled/ascii/braille. \
+                    ascii is every printable ascii character. braille is the same as --mode braille. \
+                    Any other value is used as the characters, from darkest to brightest, like ' .:-=+*#%@'")
                 .num_args(1)
                 .action(ArgAction::Set)
         )
+        .arg(
+            Arg::new("sort-chars")
+                .long("sort-chars")
+                .help("Order the characters of a custom char-set from darkest to brightest by how much space they cover")
+                .requires("char-set")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("glyph-metric")
+                .long("glyph-metric")
+                .help("\
+                    How characters are matched with pixels in glyph mode. \
+                    error(default) picks the closest brightness, ssim cares more about shape")
+                .value_name("error")
+                .num_args(1)
+                .value_parser(["error", "ssim"])
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("dither")
+                .long("dither")
+                .help("\
+                    Dither brightness when picking characters, blocks and braille dots, \
+                    and colors when using 16 or 256 colors. \
+                    Options: none(default)/floyd-steinberg/atkinson/bayer4/bayer8")
+                .value_name("none")
+                .num_args(1)
+                .value_parser(["none", "floyd-steinberg", "atkinson", "bayer4", "bayer8"])
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("brightness")
+                .long("brightness")
+                .help("Add to the brightness of every pixel, from -1 (black) to 1 (white)")
+                .value_name("0")
+                .num_args(1)
+                .value_parser(value_parser!(f32))
+                .allow_negative_numbers(true)
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("contrast")
+                .long("contrast")
+                .help("Multiply contrast. Above 1 increases contrast, below 1 decreases it")
+                .value_name("1")
+                .num_args(1)
+                .value_parser(value_parser!(f32))
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("gamma")
+                .long("gamma")
+                .help("Gamma correction. Above 1 brightens dark parts, below 1 darkens them")
+                .value_name("1")
+                .num_args(1)
+                .value_parser(value_parser!(f32))
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("saturation")
+                .long("saturation")
+                .help("Multiply saturation. 0 is grayscale")
+                .value_name("1")
+                .num_args(1)
+                .value_parser(value_parser!(f32))
+                .action(ArgAction::Set)
+        )
+        .arg(
+            Arg::new("invert")
+                .long("invert")
+                .help("Invert colors. Useful for terminals with a light background")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("levels")
+                .long("levels")
+                .help("\
+                    Stretch the brightness of the input. auto makes the darkest parts black and the brightest white. \
+                    equalize spreads brightness evenly, which brings out detail in dark images. \
+                    Options: none(default)/auto/equalize")
+                .value_name("none")
+                .num_args(1)
+                .value_parser(["none", "auto", "equalize"])
+                .action(ArgAction::Set)
+        )
         .arg(
             Arg::new("width")
                 .short('W')
                 .long("width")
-                .help("Image output width. Uses terminal width by default.")
-                .value_name("30")
+                .help("Output width in terminal columns. Uses terminal width by default.")
+                .value_name("60")
                 .num_args(1)
                 .value_parser(value_parser!(u32))
                 .action(ArgAction::Set)
@@ -211,12 +729,25 @@ fn create_cli() -> ArgMatches {
             Arg::new("height")
                 .short('H')
                 .long("height")
-                .help("Image output height. Uses terminal height by default.")
+                .help("Output height in terminal rows. Uses terminal height by default.")
                 .value_name("30")
                 .num_args(1)
                 .value_parser(value_parser!(u32))
                 .action(ArgAction::Set)
         )
+        .arg(
+            Arg::new("cell-aspect")
+                .long("cell-aspect")
+                .help("\
+                    Height of a terminal character divided by its width. \
+                    Asked from the terminal when printing to it, otherwise or if the terminal \
+                    doesn't tell it's 2. \
+                    Change it if images look stretched or squashed")
+                .value_name("2")
+                .num_args(1)
+                .value_parser(value_parser!(f32))
+                .action(ArgAction::Set)
+        )
         .arg(
             Arg::new("no-preserve-aspect-ratio")
                 .short('p')
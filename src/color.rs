@@ -0,0 +1,188 @@
+use crate::settings::ColorDepth;
+
+/// The 16 standard terminal colors, as xterm shows them by default.
+pub const ANSI16: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+/// Color of characters without a foreground color in output that isn't shown in a terminal,
+/// like html. The default foreground of xterm.
+pub const DEFAULT_FOREGROUND: [u8; 3] = ANSI16[7];
+/// Color behind characters without a background color in output that isn't shown
+/// in a terminal, like html. The default background of xterm.
+pub const DEFAULT_BACKGROUND: [u8; 3] = ANSI16[0];
+
+// Values each channel can have in the 6x6x6 color cube of the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Creates the ansi escape sequence that sets the foreground color
+/// to the color closest to `rgb` that `depth` can show.\
+/// Returns an empty string for [`ColorDepth::None`].
+// https://stackoverflow.com/questions/4842424/list-of-ansi-color-escape-sequences
+pub fn foreground(rgb: [u8; 3], depth: ColorDepth) -> String {
+    match depth {
+        ColorDepth::None => String::new(),
+        ColorDepth::Ansi16 => {
+            let index = ansi16(rgb);
+            // Bright colors have their own codes
+            if index < 8 {
+                format!("\x1b[{}m", 30 + index)
+            } else {
+                format!("\x1b[{}m", 90 + index - 8)
+            }
+        }
+        ColorDepth::Ansi256 => format!("\x1b[38;5;{}m", ansi256(rgb)),
+        ColorDepth::TrueColor => format!("\x1b[38;2;{};{};{}m", rgb[0], rgb[1], rgb[2]),
+    }
+}
+
+/// Creates the ansi escape sequence that sets the background color
+/// to the color closest to `rgb` that `depth` can show.\
+/// Returns an empty string for [`ColorDepth::None`].
+pub fn background(rgb: [u8; 3], depth: ColorDepth) -> String {
+    match depth {
+        ColorDepth::None => String::new(),
+        ColorDepth::Ansi16 => {
+            let index = ansi16(rgb);
+            // Bright colors have their own codes
+            if index < 8 {
+                format!("\x1b[{}m", 40 + index)
+            } else {
+                format!("\x1b[{}m", 100 + index - 8)
+            }
+        }
+        ColorDepth::Ansi256 => format!("\x1b[48;5;{}m", ansi256(rgb)),
+        ColorDepth::TrueColor => format!("\x1b[48;2;{};{};{}m", rgb[0], rgb[1], rgb[2]),
+    }
+}
+
+/// The color closest to `rgb` that `depth` can show, as the terminal would show it.\
+/// Returns None for [`ColorDepth::None`].
+pub fn reduce(rgb: [u8; 3], depth: ColorDepth) -> Option<[u8; 3]> {
+    match depth {
+        ColorDepth::None => None,
+        ColorDepth::Ansi16 => Some(ANSI16[ansi16(rgb) as usize]),
+        ColorDepth::Ansi256 => Some(ansi256_rgb(ansi256(rgb))),
+        ColorDepth::TrueColor => Some(rgb),
+    }
+}
+
+/// Index of the color in [`ANSI16`] closest to `rgb`.
+pub fn ansi16(rgb: [u8; 3]) -> u8 {
+    closest(rgb, (0..16).map(|i| ANSI16[i as usize]))
+}
+
+/// Index of the color in the xterm 256 color palette closest to `rgb`.\
+/// Only the color cube and grayscale ramp are searched,
+/// the first 16 colors differ between terminals.
+pub fn ansi256(rgb: [u8; 3]) -> u8 {
+    16 + closest(rgb, (16..=255).map(ansi256_rgb))
+}
+
+/// Rgb value of a color in the xterm 256 color palette.
+pub fn ansi256_rgb(index: u8) -> [u8; 3] {
+    match index {
+        0..=15 => ANSI16[index as usize],
+        // 6x6x6 color cube
+        16..=231 => {
+            let i = index - 16;
+            [
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            ]
+        }
+        // Grayscale ramp
+        232..=255 => {
+            let v = 8 + (index - 232) * 10;
+            [v, v, v]
+        }
+    }
+}
+
+/// Color that stands out when drawn over `rgb`.
+/// A dark shade of bright colors and a light tint of dark colors.
+pub fn contrasting(rgb: [u8; 3]) -> [u8; 3] {
+    if brightness(rgb.map(f32::from)) > 127. {
+        darken(rgb, 0.35)
+    } else {
+        rgb.map(|c| (c as f32 + (255. - c as f32) * 0.65) as u8)
+    }
+}
+
+/// Perceived brightness of a color, from 0 to 255 for colors with channels in that range.
+pub(crate) fn brightness(rgb: [f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+/// Multiplies every channel of `rgb` by `factor`, from 0 (black) to 1 (unchanged).
+pub fn darken(rgb: [u8; 3], factor: f32) -> [u8; 3] {
+    rgb.map(|c| (c as f32 * factor) as u8)
+}
+
+// Position of the color in `palette` with the smallest distance to `rgb`
+fn closest(rgb: [u8; 3], palette: impl Iterator<Item = [u8; 3]>) -> u8 {
+    palette
+        .map(|p| {
+            (0..3)
+                .map(|i| (p[i] as i32 - rgb[i] as i32).pow(2))
+                .sum::<i32>()
+        })
+        .enumerate()
+        .min_by_key(|&(_, distance)| distance)
+        .map_or(0, |(i, _)| i as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pure_red() {
+        assert_eq!(ansi256([255, 0, 0]), 196);
+        assert_eq!(ansi16([255, 0, 0]), 9);
+        assert_eq!(foreground([255, 0, 0], ColorDepth::Ansi16), "\x1b[91m");
+    }
+
+    #[test]
+    fn grey_uses_grayscale_ramp() {
+        let index = ansi256([128, 128, 128]);
+        assert!((232..=255).contains(&index), "got {index}");
+        assert_eq!(ansi256_rgb(index), [128, 128, 128]);
+    }
+
+    #[test]
+    fn ansi16_codes_split_normal_and_bright() {
+        for (index, rgb) in ANSI16.iter().enumerate() {
+            let index = index as u8;
+            let (fg, bg) = if index < 8 {
+                (30 + index, 40 + index)
+            } else {
+                (90 + index - 8, 100 + index - 8)
+            };
+            assert_eq!(foreground(*rgb, ColorDepth::Ansi16), format!("\x1b[{fg}m"));
+            assert_eq!(background(*rgb, ColorDepth::Ansi16), format!("\x1b[{bg}m"));
+        }
+        // Both ends of both ranges
+        assert_eq!(foreground(ANSI16[0], ColorDepth::Ansi16), "\x1b[30m");
+        assert_eq!(foreground(ANSI16[7], ColorDepth::Ansi16), "\x1b[37m");
+        assert_eq!(foreground(ANSI16[15], ColorDepth::Ansi16), "\x1b[97m");
+        assert_eq!(background(ANSI16[8], ColorDepth::Ansi16), "\x1b[100m");
+        assert_eq!(background(ANSI16[15], ColorDepth::Ansi16), "\x1b[107m");
+    }
+}
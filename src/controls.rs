@@ -0,0 +1,67 @@
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use std::io::{stdout, Write};
+use std::time::Duration;
+
+/// What a key press asks playback to do.
+pub(crate) enum Control {
+    /// Pause or resume
+    Pause,
+    /// Move by this many seconds
+    Seek(i64),
+    /// Change speed by this much
+    Speed(f32),
+    Quit,
+}
+
+/// Reads playback controls from the keyboard.\
+/// Keeps the terminal in raw mode so single key presses can be read,
+/// the terminal is restored when this is dropped.
+pub(crate) struct Controls;
+
+impl Controls {
+    pub(crate) fn new() -> anyhow::Result<Controls> {
+        terminal::enable_raw_mode()?;
+        Ok(Controls)
+    }
+    /// Waits up to `timeout` for a key press.\
+    /// Returns None if no key was pressed or the key doesn't control playback.
+    ///
+    /// Keys: space pauses, left and right arrows seek 5 seconds,
+    /// `+` and `-` change speed, `q`, escape and ctrl-c quit.
+    pub(crate) fn read(&self, timeout: Duration) -> anyhow::Result<Option<Control>> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+        let control = match event::read()? {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => match code {
+                KeyCode::Char(' ') => Some(Control::Pause),
+                KeyCode::Left => Some(Control::Seek(-5)),
+                KeyCode::Right => Some(Control::Seek(5)),
+                KeyCode::Char('+') | KeyCode::Char('=') => Some(Control::Speed(0.25)),
+                KeyCode::Char('-') => Some(Control::Speed(-0.25)),
+                KeyCode::Char('q') | KeyCode::Esc => Some(Control::Quit),
+                // Raw mode turns ctrl-c into a key press
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    Some(Control::Quit)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        Ok(control)
+    }
+}
+
+impl Drop for Controls {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+        // Turns all ansi attributes off
+        let _ = writeln!(stdout(), "\x1b[0m").and_then(|_| stdout().flush());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::frame::{self, AsciiFrame};
+use crate::settings::ColorDepth;
+
+/// Turns ascii frames into the terminal output needed to draw them over the previous frame.\
+/// Instead of clearing the screen and printing the whole frame, only the characters
+/// that changed since the previous frame are printed, each moved into place with
+/// cursor positioning escapes.
+#[derive(Debug, Default)]
+pub(crate) struct DeltaRenderer {
+    prev: Option<AsciiFrame>,
+}
+
+impl DeltaRenderer {
+    /// Returns what has to be written to the terminal to show `frame`.\
+    /// The first frame, or a frame with a different size, is drawn whole.
+    pub(crate) fn render(&mut self, frame: &AsciiFrame, depth: ColorDepth) -> String {
+        let prev = match self.prev.take() {
+            Some(prev) if (prev.width, prev.height) == (frame.width, frame.height) => prev,
+            _ => {
+                self.prev = Some(frame.clone());
+                // Clear screen and move cursor to top left corner before drawing
+                return format!("\x1b[2J\x1b[H{}", frame.to_ansi(depth));
+            }
+        };
+
+        let mut out = String::new();
+        // Where the terminal cursor is, as (row, column)
+        let mut cursor = None;
+        // Colors currently in effect, as (foreground, background)
+        let mut colors = (None, None);
+        let mut colored = false;
+
+        for (y, (row, prev_row)) in frame.rows().zip(prev.rows()).enumerate() {
+            for (x, (cell, prev_cell)) in row.iter().zip(prev_row).enumerate() {
+                if cell == prev_cell {
+                    continue;
+                }
+                // Move cursor to cell, ansi positions start at 1
+                if cursor != Some((y, x)) {
+                    out.push_str(&format!("\x1b[{};{}H", y + 1, x + 1));
+                }
+                // Change color if needed
+                if depth != ColorDepth::None && (cell.fg, cell.bg) != colors {
+                    out.push_str(&frame::sgr(cell, depth, colors));
+                    colors = (cell.fg, cell.bg);
+                    colored = true;
+                }
+                out.push(cell.glyph);
+                cursor = Some((y, x + 1));
+            }
+        }
+        // Turns all ansi attributes off
+        if colored {
+            out.push_str("\x1b[0m");
+        }
+
+        self.prev = Some(frame.clone());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::AsciiCell;
+
+    // Frame of `width` x `height` red '#' characters
+    fn frame(width: u32, height: u32) -> AsciiFrame {
+        let mut frame = AsciiFrame::new(width, height);
+        for cell in &mut frame.cells {
+            *cell = AsciiCell {
+                glyph: '#',
+                fg: Some([255, 0, 0]),
+                bg: None,
+            };
+        }
+        frame
+    }
+
+    #[test]
+    fn first_frame_is_drawn_whole() {
+        let mut renderer = DeltaRenderer::default();
+        let first = frame(4, 3);
+        let out = renderer.render(&first, ColorDepth::TrueColor);
+        assert_eq!(
+            out,
+            format!("\x1b[2J\x1b[H{}", first.to_ansi(ColorDepth::TrueColor))
+        );
+    }
+
+    #[test]
+    fn identical_frame_draws_nothing() {
+        let mut renderer = DeltaRenderer::default();
+        renderer.render(&frame(4, 3), ColorDepth::TrueColor);
+        assert_eq!(renderer.render(&frame(4, 3), ColorDepth::TrueColor), "");
+    }
+
+    #[test]
+    fn changed_cell_is_moved_to_and_drawn() {
+        let mut renderer = DeltaRenderer::default();
+        renderer.render(&frame(4, 3), ColorDepth::TrueColor);
+
+        let mut next = frame(4, 3);
+        let cell = next.get_mut(2, 1).unwrap();
+        cell.glyph = '@';
+        cell.fg = Some([0, 0, 255]);
+        let sgr = frame::sgr(next.get(2, 1).unwrap(), ColorDepth::TrueColor, (None, None));
+
+        let out = renderer.render(&next, ColorDepth::TrueColor);
+        // One cursor move to row 2, column 3, then the color and the character
+        assert_eq!(out, format!("\x1b[2;3H{sgr}@\x1b[0m"));
+        assert_eq!(out.matches('H').count(), 1);
+    }
+
+    #[test]
+    fn size_change_draws_whole_frame() {
+        let mut renderer = DeltaRenderer::default();
+        renderer.render(&frame(4, 3), ColorDepth::TrueColor);
+        let bigger = frame(5, 3);
+        assert_eq!(
+            renderer.render(&bigger, ColorDepth::TrueColor),
+            format!("\x1b[2J\x1b[H{}", bigger.to_ansi(ColorDepth::TrueColor))
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use crate::color;
+use crate::settings::{ColorDepth, Dither};
+use image::RgbImage;
+
+// 4x4 bayer matrix for ordered dithering
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// 8x8 bayer matrix for ordered dithering
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+// Where the error of a pixel is spread to, as (x offset, y offset, share of the error)
+const FLOYD_STEINBERG: [(i64, i64, f32); 4] = [
+    (1, 0, 7. / 16.),
+    (-1, 1, 3. / 16.),
+    (0, 1, 5. / 16.),
+    (1, 1, 1. / 16.),
+];
+// Only spreads 3/4 of the error, which keeps more contrast
+const ATKINSON: [(i64, i64, f32); 6] = [
+    (1, 0, 1. / 8.),
+    (2, 0, 1. / 8.),
+    (-1, 1, 1. / 8.),
+    (0, 1, 1. / 8.),
+    (1, 1, 1. / 8.),
+    (0, 2, 1. / 8.),
+];
+
+/// Quantizes values from 0 to 1, row by row with `width` values per row,
+/// to indices from 0 to `levels - 1`.\
+/// Without dithering every value is rounded to the closest level.
+pub(crate) fn levels(values: &[f32], width: usize, levels: usize, method: Dither) -> Vec<usize> {
+    let max = levels.saturating_sub(1) as f32;
+    let mut values: Vec<[f32; 1]> = values.iter().map(|v| [v * max]).collect();
+
+    dither(&mut values, width, method, 1., |[v]| {
+        [v.round().clamp(0., max)]
+    });
+    values.iter().map(|[v]| *v as usize).collect()
+}
+
+/// Reduces the colors of an image to the palette of `depth`, dithering them with `method`.\
+/// Only 16 and 256 colors have a palette, other depths and [`Dither::None`]
+/// return the image as is.
+pub(crate) fn palette(img: &RgbImage, depth: ColorDepth, method: Dither) -> RgbImage {
+    if method == Dither::None {
+        return img.clone();
+    }
+    // How far ordered dithering moves colors, about the distance between palette colors
+    let spread = match depth {
+        ColorDepth::Ansi16 => 128.,
+        ColorDepth::Ansi256 => 40.,
+        ColorDepth::None | ColorDepth::TrueColor => return img.clone(),
+    };
+
+    let mut values: Vec<[f32; 3]> = img.pixels().map(|p| p.0.map(|c| c as f32)).collect();
+    dither(&mut values, img.width() as usize, method, spread, |rgb| {
+        let rgb = rgb.map(|c| c.round().clamp(0., 255.) as u8);
+        let closest = match depth {
+            ColorDepth::Ansi16 => color::ANSI16[color::ansi16(rgb) as usize],
+            _ => color::ansi256_rgb(color::ansi256(rgb)),
+        };
+        closest.map(|c| c as f32)
+    });
+
+    let mut img = img.clone();
+    for (p, rgb) in img.pixels_mut().zip(values) {
+        p.0 = rgb.map(|c| c as u8);
+    }
+    img
+}
+
+// Replaces every value with its quantized value, row by row with `width` values per row.
+// Error diffusion spreads the difference to the following values, ordered dithering
+// offsets every value by up to half of `spread` before quantizing
+fn dither<const N: usize>(
+    values: &mut [[f32; N]],
+    width: usize,
+    method: Dither,
+    spread: f32,
+    quantize: impl Fn([f32; N]) -> [f32; N],
+) {
+    let kernel: &[(i64, i64, f32)] = match method {
+        Dither::FloydSteinberg => &FLOYD_STEINBERG,
+        Dither::Atkinson => &ATKINSON,
+        _ => &[],
+    };
+    let height = values.len() / width.max(1);
+
+    for i in 0..values.len() {
+        let (x, y) = (i % width, i / width);
+        let offset = match method {
+            Dither::Bayer4 => (BAYER_4X4[y % 4][x % 4] as f32 + 0.5) / 16. - 0.5,
+            Dither::Bayer8 => (BAYER_8X8[y % 8][x % 8] as f32 + 0.5) / 64. - 0.5,
+            _ => 0.,
+        };
+        let old = values[i];
+        let new = quantize(old.map(|v| v + offset * spread));
+        values[i] = new;
+
+        for (dx, dy, share) in kernel {
+            let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+            if nx < 0 || nx >= width as i64 || ny >= height as i64 {
+                continue;
+            }
+            let neighbour = &mut values[ny as usize * width + nx as usize];
+            for c in 0..N {
+                neighbour[c] += (old[c] - new[c]) * share;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_dither_rounds() {
+        let values = [0., 0.1, 0.2, 0.45, 0.55, 0.9, 1.];
+        assert_eq!(
+            levels(&values, values.len(), 3, Dither::None),
+            [0, 0, 0, 1, 1, 2, 2]
+        );
+    }
+
+    #[test]
+    fn flat_half_uses_both_levels_equally() {
+        let values = [0.5; 16 * 16];
+        for method in [Dither::FloydSteinberg, Dither::Bayer4, Dither::Bayer8] {
+            let quantized = levels(&values, 16, 2, method);
+            assert!(quantized.iter().all(|&level| level < 2));
+            let high = quantized.iter().filter(|&&level| level == 1).count();
+            // Half of the 256 values, give or take a few
+            assert!((118..=138).contains(&high), "{method:?}: {high} of 256");
+        }
+    }
+}
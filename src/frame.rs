@@ -0,0 +1,195 @@
+use crate::color;
+use crate::html;
+use crate::raster;
+use crate::settings::{ColorDepth, RasterSettings, SvgSettings, TextSettings};
+use crate::svg;
+use image::RgbImage;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// One character of an [`AsciiFrame`].
+pub struct AsciiCell {
+    pub glyph: char,
+    /// Color of the character. None uses the terminal's default color.
+    pub fg: Option<[u8; 3]>,
+    /// Color behind the character. None uses the terminal's default background.
+    pub bg: Option<[u8; 3]>,
+}
+
+impl Default for AsciiCell {
+    fn default() -> AsciiCell {
+        AsciiCell {
+            glyph: ' ',
+            fg: None,
+            bg: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// An ascii image or video frame as a grid of characters with their colors.\
+/// Made by converting an image, see [`AsciiImage`](crate::AsciiImage).
+/// Serialize it to show or save it, for example with [`AsciiFrame::to_ansi`].
+pub struct AsciiFrame {
+    /// Number of columns
+    pub width: u32,
+    /// Number of rows
+    pub height: u32,
+    /// Cells row by row from the top left corner, `width * height` of them.
+    pub cells: Vec<AsciiCell>,
+}
+
+impl AsciiFrame {
+    /// Frame of `width` x `height` empty cells.
+    pub fn new(width: u32, height: u32) -> AsciiFrame {
+        AsciiFrame {
+            width,
+            height,
+            cells: vec![AsciiCell::default(); (width * height) as usize],
+        }
+    }
+    /// Cell at column `x` and row `y`, None if it's outside the frame.
+    pub fn get(&self, x: u32, y: u32) -> Option<&AsciiCell> {
+        if x < self.width && y < self.height {
+            self.cells.get((y * self.width + x) as usize)
+        } else {
+            None
+        }
+    }
+    /// Mutable cell at column `x` and row `y`, None if it's outside the frame.
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut AsciiCell> {
+        if x < self.width && y < self.height {
+            self.cells.get_mut((y * self.width + x) as usize)
+        } else {
+            None
+        }
+    }
+    /// Cells of every row from the top.
+    pub fn rows(&self) -> impl Iterator<Item = &[AsciiCell]> {
+        // chunks panics on 0, a frame without columns has no cells anyway
+        self.cells.chunks(self.width.max(1) as usize)
+    }
+    /// Text with ansi escape sequences that shows the frame in a terminal,
+    /// with colors reduced to what `depth` can show.\
+    /// Ends with a reset of all ansi attributes, unless `depth` is [`ColorDepth::None`]
+    /// in which case there are no escape sequences at all.
+    pub fn to_ansi(&self, depth: ColorDepth) -> String {
+        let mut ansi = String::with_capacity(self.cells.len() * 4);
+        // Colors currently in effect
+        let mut current = (None, None);
+
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                // Reset before newline so colors don't spill into the next line
+                if current != (None, None) {
+                    ansi.push_str("\x1b[0m");
+                    current = (None, None);
+                }
+                ansi.push('\n');
+            }
+            for cell in row {
+                if depth != ColorDepth::None && (cell.fg, cell.bg) != current {
+                    ansi.push_str(&sgr(cell, depth, current));
+                    current = (cell.fg, cell.bg);
+                }
+                ansi.push(cell.glyph);
+            }
+        }
+        // Turns all ansi attributes off
+        if depth != ColorDepth::None {
+            ansi.push_str("\x1b[0m");
+        }
+        ansi
+    }
+    /// Html `<pre>` element that shows the frame, with colors reduced to what `depth`
+    /// can show.\
+    /// Runs of characters with the same colors share a `<span>`.
+    /// Characters without colors use the default colors of [`crate::color`].
+    pub fn to_html(&self, depth: ColorDepth) -> String {
+        html::pre(self, depth)
+    }
+    /// Svg document that shows the frame with text in the font of `settings`,
+    /// with colors reduced to what `depth` can show.\
+    /// Characters without colors use the default colors of [`crate::color`].
+    pub fn to_svg(&self, depth: ColorDepth, settings: &SvgSettings) -> String {
+        svg::document(self, depth, settings)
+    }
+    /// Image of the frame drawn with the built-in 8x8 bitmap font,
+    /// with colors reduced to what `depth` can show.\
+    /// The font has the printable ascii characters and the block and braille characters
+    /// paxcii uses, other characters are drawn as `?`.
+    pub fn to_image(&self, depth: ColorDepth, settings: &RasterSettings) -> RgbImage {
+        raster::rasterize(self, depth, settings)
+    }
+    /// Only the characters of the frame, without colors or escape sequences.
+    /// Every line, including the last one, ends with the line ending of `settings`.
+    pub fn to_plain(&self, settings: &TextSettings) -> String {
+        let line_ending = settings.line_ending.as_str();
+        let mut plain =
+            String::with_capacity(self.cells.len() + self.height as usize * line_ending.len());
+        for row in self.rows() {
+            let line: String = row.iter().map(|cell| cell.glyph).collect();
+            if settings.trim {
+                plain.push_str(line.trim_end());
+            } else {
+                plain.push_str(&line);
+            }
+            plain.push_str(line_ending);
+        }
+        plain
+    }
+}
+
+/// Ansi escape sequences that change the colors from `current`, as (foreground, background),
+/// to the colors of `cell`.
+pub(crate) fn sgr(
+    cell: &AsciiCell,
+    depth: ColorDepth,
+    current: (Option<[u8; 3]>, Option<[u8; 3]>),
+) -> String {
+    let mut sgr = String::new();
+    // A color can only be removed by resetting all of them
+    if (current.0.is_some() && cell.fg.is_none()) || (current.1.is_some() && cell.bg.is_none()) {
+        sgr.push_str("\x1b[0m");
+    }
+    if let Some(fg) = cell.fg {
+        sgr.push_str(&color::foreground(fg, depth));
+    }
+    if let Some(bg) = cell.bg {
+        sgr.push_str(&color::background(bg, depth));
+    }
+    sgr
+}
+
+// Cells next to each other in a row with the same colors, as `depth` shows them
+pub(crate) struct Run {
+    // Column of the first cell
+    pub(crate) start: usize,
+    // Number of cells
+    pub(crate) len: usize,
+    pub(crate) text: String,
+    pub(crate) fg: Option<[u8; 3]>,
+    pub(crate) bg: Option<[u8; 3]>,
+}
+
+// Splits `row` into runs of cells with the same colors, as `depth` shows them
+pub(crate) fn runs(row: &[AsciiCell], depth: ColorDepth) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for (x, cell) in row.iter().enumerate() {
+        let fg = cell.fg.and_then(|fg| color::reduce(fg, depth));
+        let bg = cell.bg.and_then(|bg| color::reduce(bg, depth));
+        match runs.last_mut() {
+            Some(run) if (run.fg, run.bg) == (fg, bg) => {
+                run.text.push(cell.glyph);
+                run.len += 1;
+            }
+            _ => runs.push(Run {
+                start: x,
+                len: 1,
+                text: cell.glyph.to_string(),
+                fg,
+                bg,
+            }),
+        }
+    }
+    runs
+}
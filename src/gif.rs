@@ -0,0 +1,65 @@
+use crate::frame::AsciiFrame;
+use crate::raster;
+use crate::settings::{ColorDepth, GifSettings, RasterSettings};
+use anyhow::bail;
+use color_quant::NeuQuant;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, RgbaImage};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufWriter;
+
+// Speed of the quantization of frames with too many colors, from 1 (best) to 30 (fastest)
+const QUANTIZE_SPEED: i32 = 10;
+
+/// Writes `frames` to `path` as a gif that plays at `fps` frames per second.
+pub(crate) fn write(
+    path: &str,
+    frames: &[AsciiFrame],
+    fps: f32,
+    depth: ColorDepth,
+    raster: &RasterSettings,
+    settings: &GifSettings,
+) -> anyhow::Result<()> {
+    if fps <= 0. {
+        bail!("Can't write gif with {fps} fps");
+    }
+    let palette_size = settings.palette_size.unwrap_or(256).clamp(2, 256) as usize;
+
+    let mut encoder =
+        GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), QUANTIZE_SPEED);
+    encoder.set_repeat(match settings.repeat {
+        Some(repeat) => Repeat::Finite(repeat),
+        None => Repeat::Infinite,
+    })?;
+
+    for (i, frame) in frames.iter().enumerate() {
+        let mut image = DynamicImage::ImageRgb8(raster::rasterize(frame, depth, raster)).to_rgba8();
+        limit_palette(&mut image, palette_size);
+        encoder.encode_frame(Frame::from_parts(image, 0, 0, delay(i, fps)))?;
+    }
+    Ok(())
+}
+
+// Gif delays are in hundredths of a second. Every frame ends at its rounded
+// time since the start, so the rounding doesn't add up and the gif keeps to `fps`
+fn delay(index: usize, fps: f32) -> Delay {
+    let end = |index: usize| (index as f64 * 100. / fps as f64).round() as u32;
+    Delay::from_numer_denom_ms((end(index + 1) - end(index)) * 10, 1)
+}
+
+// Quantizes the colors of `image` to `palette_size` colors if it has more
+fn limit_palette(image: &mut RgbaImage, palette_size: usize) {
+    let mut colors = HashSet::new();
+    let too_many = image
+        .pixels()
+        .any(|p| colors.insert(p.0) && colors.len() > palette_size);
+    if !too_many {
+        return;
+    }
+
+    let quant = NeuQuant::new(QUANTIZE_SPEED, palette_size, image.as_raw());
+    for pixel in image.pixels_mut() {
+        quant.map_pixel(&mut pixel.0);
+    }
+}
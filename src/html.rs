@@ -0,0 +1,154 @@
+use crate::color::{DEFAULT_BACKGROUND, DEFAULT_FOREGROUND};
+use crate::frame::{self, AsciiFrame};
+use crate::settings::ColorDepth;
+
+// Style of the `<pre>` holding the ascii. Monospace fonts are about twice as tall
+// as they are wide with this line height, like terminal cells
+fn pre_style() -> String {
+    format!(
+        "font-family:monospace;line-height:1.2;color:{};background-color:{};display:inline-block;margin:0",
+        hex(DEFAULT_FOREGROUND),
+        hex(DEFAULT_BACKGROUND)
+    )
+}
+
+/// `<pre>` element showing `frame`, with a `<span>` for every run of characters
+/// with the same colors.
+pub(crate) fn pre(frame: &AsciiFrame, depth: ColorDepth) -> String {
+    format!(
+        "<pre style=\"{}\">{}</pre>",
+        pre_style(),
+        spans(frame, depth)
+    )
+}
+
+// Content of the `<pre>` of `frame`
+fn spans(frame: &AsciiFrame, depth: ColorDepth) -> String {
+    let mut html = String::with_capacity(frame.cells.len() * 2);
+
+    for (y, row) in frame.rows().enumerate() {
+        if y > 0 {
+            html.push('\n');
+        }
+        for run in frame::runs(row, depth) {
+            let text = escape(&run.text);
+            let mut style = String::new();
+            if let Some(fg) = run.fg {
+                style.push_str(&format!("color:{}", hex(fg)));
+            }
+            if let Some(bg) = run.bg {
+                if !style.is_empty() {
+                    style.push(';');
+                }
+                style.push_str(&format!("background-color:{}", hex(bg)));
+            }
+            if style.is_empty() {
+                html.push_str(&text);
+            } else {
+                html.push_str(&format!("<span style=\"{style}\">{text}</span>"));
+            }
+        }
+    }
+    html
+}
+
+/// Html page showing `body`.
+pub(crate) fn document(body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n\
+        <html>\n\
+        <head>\n\
+        <meta charset=\"utf-8\">\n\
+        <title>paxcii</title>\n\
+        </head>\n\
+        <body style=\"background-color:{}\">\n\
+        {body}\n\
+        </body>\n\
+        </html>\n",
+        hex(DEFAULT_BACKGROUND)
+    )
+}
+
+/// Html page that plays `frames` at `fps` frames per second.\
+/// Clicking the video pauses and resumes it.
+pub(crate) fn player(frames: &[AsciiFrame], depth: ColorDepth, fps: f32) -> String {
+    let mut script = String::from("const frames = [\n");
+    for frame in frames {
+        script.push_str(&js_string(&spans(frame, depth)));
+        script.push_str(",\n");
+    }
+    script.push_str("];\n");
+    script.push_str(&format!("const fps = {fps};\n"));
+    // Frames are picked by the time since the start, so slow drawing skips frames
+    // instead of slowing the video down
+    script.push_str(
+        "const pre = document.getElementById(\"ascii\");\n\
+        let start = null;\n\
+        let pausedAt = null;\n\
+        let shown = -1;\n\
+        pre.addEventListener(\"click\", () => {\n\
+        \x20   if (pausedAt === null) {\n\
+        \x20       pausedAt = performance.now();\n\
+        \x20   } else {\n\
+        \x20       start += performance.now() - pausedAt;\n\
+        \x20       pausedAt = null;\n\
+        \x20       requestAnimationFrame(draw);\n\
+        \x20   }\n\
+        });\n\
+        function draw(now) {\n\
+        \x20   if (pausedAt !== null) return;\n\
+        \x20   if (start === null) start = now;\n\
+        \x20   const index = Math.floor((now - start) / 1000 * fps);\n\
+        \x20   if (index >= frames.length) return;\n\
+        \x20   if (index !== shown) {\n\
+        \x20       pre.innerHTML = frames[index];\n\
+        \x20       shown = index;\n\
+        \x20   }\n\
+        \x20   requestAnimationFrame(draw);\n\
+        }\n\
+        requestAnimationFrame(draw);\n",
+    );
+
+    document(&format!(
+        "<pre id=\"ascii\" style=\"{}\"></pre>\n<script>\n{script}</script>",
+        pre_style()
+    ))
+}
+
+// Css color of `rgb`, also used by svg
+pub(crate) fn hex(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+// `text` with the characters that have a meaning in html or svg escaped
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+// Javascript string literal of `s`
+fn js_string(s: &str) -> String {
+    let mut js = String::with_capacity(s.len() + 2);
+    js.push('"');
+    for ch in s.chars() {
+        match ch {
+            '\\' => js.push_str("\\\\"),
+            '"' => js.push_str("\\\""),
+            '\n' => js.push_str("\\n"),
+            // `</script>` in a string would end the script
+            '/' => js.push_str("\\/"),
+            _ => js.push(ch),
+        }
+    }
+    js.push('"');
+    js
+}
@@ -1,12 +1,21 @@
-use crate::settings::PaxciiSettings;
+use crate::adjust;
+use crate::color;
+use crate::dither;
+use crate::font::{self, GLYPH_SIZE};
+use crate::frame::{AsciiCell, AsciiFrame};
+use crate::html;
+use crate::settings::{
+    Background, ColorDepth, Dither, GlyphMetric, PaxciiSettings, RasterSettings, RenderMode,
+    SvgSettings, TextSettings,
+};
 use image::io::Reader as ImageReader;
-use image::{imageops::FilterType, DynamicImage};
+use image::{imageops::FilterType, DynamicImage, ImageFormat, RgbImage};
 use std::fs;
 
 pub struct AsciiImage {
     pub settings: PaxciiSettings,
     pub image: Option<DynamicImage>,
-    pub ascii: Option<String>,
+    pub ascii: Option<AsciiFrame>,
 }
 
 impl AsciiImage {
@@ -15,7 +24,7 @@ impl AsciiImage {
     pub fn open(path: &str, settings: PaxciiSettings) -> anyhow::Result<AsciiImage> {
         let mut ascii_image = AsciiImage::new(settings);
         ascii_image.image = Some(ImageReader::open(path)?.decode()?);
-        ascii_image.image_to_ascii(true);
+        ascii_image.image_to_ascii(true)?;
 
         Ok(ascii_image)
     }
@@ -28,16 +37,61 @@ impl AsciiImage {
     }
     /// Prints the ascii to terminal.
     pub fn print(&self) {
-        if self.ascii.is_none() {
-            eprintln!("`ascii` is None. No image to print");
+        if let Some(ascii) = &self.ascii {
+            println!("{}", ascii.to_ansi(self.settings.color))
         } else {
-            println!("{}", self.ascii.as_ref().unwrap())
+            eprintln!("`ascii` is None. No image to print");
         }
     }
-    /// Writes ascii image to file.
+    /// Writes ascii image to file, with ansi escape sequences for colors.
     pub fn write(&self, path: &str) -> anyhow::Result<()> {
         if let Some(ascii) = &self.ascii {
-            fs::write(path, ascii)?;
+            fs::write(path, ascii.to_ansi(self.settings.color))?;
+            Ok(())
+        } else {
+            anyhow::bail!("Couldn't write ascii image to file. Ascii missing")
+        }
+    }
+    /// Writes ascii image to file as a self-contained html page.
+    pub fn write_html(&self, path: &str) -> anyhow::Result<()> {
+        if let Some(ascii) = &self.ascii {
+            fs::write(path, html::document(&ascii.to_html(self.settings.color)))?;
+            Ok(())
+        } else {
+            anyhow::bail!("Couldn't write ascii image to file. Ascii missing")
+        }
+    }
+    /// Writes ascii image to file as an svg image.
+    pub fn write_svg(&self, path: &str, settings: &SvgSettings) -> anyhow::Result<()> {
+        if let Some(ascii) = &self.ascii {
+            fs::write(path, ascii.to_svg(self.settings.color, settings))?;
+            Ok(())
+        } else {
+            anyhow::bail!("Couldn't write ascii image to file. Ascii missing")
+        }
+    }
+    /// Writes ascii image to file as an image drawn with the built-in bitmap font,
+    /// see [`AsciiFrame::to_image`].\
+    /// `format` is the image format, like png or jpeg.
+    pub fn write_image(
+        &self,
+        path: &str,
+        format: ImageFormat,
+        settings: &RasterSettings,
+    ) -> anyhow::Result<()> {
+        if let Some(ascii) = &self.ascii {
+            ascii
+                .to_image(self.settings.color, settings)
+                .save_with_format(path, format)?;
+            Ok(())
+        } else {
+            anyhow::bail!("Couldn't write ascii image to file. Ascii missing")
+        }
+    }
+    /// Writes ascii image to file as plain text, without colors or escape sequences.
+    pub fn write_text(&self, path: &str, settings: &TextSettings) -> anyhow::Result<()> {
+        if let Some(ascii) = &self.ascii {
+            fs::write(path, ascii.to_plain(settings))?;
             Ok(())
         } else {
             anyhow::bail!("Couldn't write ascii image to file. Ascii missing")
@@ -45,83 +99,417 @@ impl AsciiImage {
     }
     /// Transforms an image into ascii.\
     /// Also optionally resizes image.
-    pub fn image_to_ascii(&mut self, resize: bool) {
+    ///
+    /// Without resizing the image must already be the size the render mode needs,
+    /// see [`PaxciiSettings::sample_size`].
+    ///
+    /// Fails if `image` is not set, or if `char_set` has less than two characters
+    /// in render modes that draw them.
+    pub fn image_to_ascii(&mut self, resize: bool) -> anyhow::Result<()> {
         if self.image.is_none() {
-            eprintln!("`image` parameter is not set. Can't convert image to ascii");
-            return;
+            anyhow::bail!("`image` parameter is not set. Can't convert image to ascii");
+        }
+        let uses_char_set = matches!(
+            self.settings.mode,
+            RenderMode::Chars | RenderMode::Structural | RenderMode::Glyph
+        );
+        if uses_char_set && self.settings.char_set.len() < 2 {
+            anyhow::bail!(
+                "`char_set` needs at least two characters, got {}. Can't convert image to ascii",
+                self.settings.char_set.len()
+            );
         }
         let img = self.image.as_ref().unwrap();
 
         // Resize image to fit desired ascii output size
-        let img = if resize {
-            if self.settings.keep_aspect_ratio {
-                img.resize(
-                    self.settings.width,
-                    self.settings.height,
-                    FilterType::Triangle,
-                )
+        let mut img = if resize {
+            let size = self.settings.grid_size((img.width(), img.height()));
+            // Some render modes draw more than one image pixel per character
+            let (scale_w, scale_h) = self.settings.mode.scale();
+            img.resize_exact(size.0 * scale_w, size.1 * scale_h, FilterType::Triangle)
                 .to_rgb8()
-            } else {
-                img.resize_exact(
-                    self.settings.width,
-                    self.settings.height,
-                    FilterType::Triangle,
-                )
-                .to_rgb8()
-            }
         } else {
             img.to_rgb8()
         };
+        adjust::apply(&mut img, &self.settings.adjust);
 
-        // The ascii image that this function will return
-        // the defined capacity is an approximation
-        let mut ascii_img = String::with_capacity(img.len() / 3 * 2);
+        // Colors reduced to the palette of the color depth, characters are still picked
+        // from the brightness of the original image
+        let colors = dither::palette(&img, self.settings.color, self.settings.dither);
 
-        // For keeping track of position in image width
-        let mut row_index = 1;
+        self.ascii = Some(match self.settings.mode {
+            RenderMode::Chars => chars(&img, &colors, &self.settings),
+            RenderMode::HalfBlock => half_blocks(&img, &colors, &self.settings),
+            RenderMode::Braille => braille(&img, &colors, &self.settings),
+            RenderMode::Structural => structural(&img, &colors, &self.settings),
+            RenderMode::Glyph => glyphs(&img, &colors, &self.settings),
+        });
+        Ok(())
+    }
+}
+
+// Draws every pixel as a character from `char_set` chosen by brightness
+fn chars(img: &RgbImage, colors: &RgbImage, settings: &PaxciiSettings) -> AsciiFrame {
+    let mut cells = Vec::with_capacity((img.width() * img.height()) as usize);
+
+    // What ASCII character to use for each pixel
+    let shades = shades(img, settings);
+
+    for y in 0..img.height() {
+        for x in 0..img.width() {
+            let ch = settings.char_set[shades[(y * img.width() + x) as usize]];
+            let p = colors.get_pixel(x, y).0;
+            cells.push(ascii_cell(ch, p, p, settings));
+        }
+    }
+    frame(img.width(), img.height(), cells)
+}
 
-        for p in img.pixels() {
-            // If at end of pixel row print newline
-            if row_index == img.width() {
-                ascii_img += "\n";
-                row_index = 1;
+// Index in `char_set` for every pixel, brighter pixels get characters further in the set
+fn shades(img: &RgbImage, settings: &PaxciiSettings) -> Vec<usize> {
+    let brightness: Vec<f32> = img
+        .pixels()
+        .map(|p| {
+            let p = p.0;
+            let brightness = if settings.color != ColorDepth::None {
+                0.267 * p[0] as f32 + 0.642 * p[1] as f32 + 0.091 * p[2] as f32
             } else {
-                // Determine what ASCII character to use for this pixel
-                let brightness = if self.settings.color {
-                    0.267 * p.0[0] as f32 + 0.642 * p.0[1] as f32 + 0.091 * p.0[2] as f32
-                } else {
-                    0.2126 * p.0[0] as f32 + 0.7152 * p.0[1] as f32 + 0.0722 * p.0[2] as f32
-                };
-                let size = self.settings.char_set.len() - 1;
-                let char_set_index = (size as f32 * brightness / 255.).round() as usize;
-
-                // Creates the ascii pixel from two ascii characters, colors it if needed
-                let ascii_pixel = if self.settings.color {
-                    truecolor(p.0, self.settings.char_set[char_set_index])
-                } else {
-                    format!(
-                        "{}{}",
-                        self.settings.char_set[char_set_index],
-                        self.settings.char_set[char_set_index]
-                    )
+                color::brightness(p.map(f32::from))
+            };
+            brightness / 255.
+        })
+        .collect();
+    dither::levels(
+        &brightness,
+        img.width() as usize,
+        settings.char_set.len(),
+        settings.dither,
+    )
+}
+
+// True for every pixel that is bright enough to be drawn without color
+fn lit(img: &RgbImage, dither: Dither) -> Vec<bool> {
+    let brightness: Vec<f32> = img
+        .pixels()
+        .map(|p| color::brightness(p.0.map(f32::from)) / 255.)
+        .collect();
+    dither::levels(&brightness, img.width() as usize, 2, dither)
+        .iter()
+        .map(|&level| level == 1)
+        .collect()
+}
+
+// Gradient magnitude above which a pixel is drawn as an edge
+const EDGE_THRESHOLD: f32 = 160.;
+
+// Draws every pixel as a character like `chars`, but pixels on an edge
+// are drawn as a line character that follows the direction of the edge
+fn structural(img: &RgbImage, colors: &RgbImage, settings: &PaxciiSettings) -> AsciiFrame {
+    let mut cells = Vec::with_capacity((img.width() * img.height()) as usize);
+    let gradients = sobel(img);
+    let shades = shades(img, settings);
+    let (w, h) = (img.width() as i64, img.height() as i64);
+    // Gradient magnitude at a position, zero outside of the image
+    let magnitude = |x: i64, y: i64| {
+        if x < 0 || y < 0 || x >= w || y >= h {
+            0.
+        } else {
+            let (gx, gy) = gradients[(y * w + x) as usize];
+            gx.hypot(gy)
+        }
+    };
+
+    for y in 0..h {
+        for x in 0..w {
+            let p = colors.get_pixel(x as u32, y as u32).0;
+            let (gx, gy) = gradients[(y * w + x) as usize];
+            let m = gx.hypot(gy);
+
+            // Direction of the gradient on screen from 0 to 180 degrees, the edge is
+            // perpendicular to it. Pixels are as tall as the cells, so vertical change is
+            // spread over more space. y grows downwards so a gradient pointing
+            // right and down is a `/` edge
+            let angle = (gy / settings.cell_aspect)
+                .atan2(gx)
+                .to_degrees()
+                .rem_euclid(180.);
+            // Rounded to the closest of the four directions that have a line character,
+            // with the neighbours across the edge. Diagonal edges are stairs of pixels,
+            // so the pixels next to them are across the edge too
+            let (ch, across): (char, &[(i64, i64)]) = match ((angle + 22.5) / 45.) as u32 % 4 {
+                0 => ('|', &[(1, 0)]),
+                1 => ('/', &[(1, 1), (1, 0), (0, 1)]),
+                // Edges closer to the pixel below than the one above sit low in the character
+                2 if magnitude(x, y + 1) > magnitude(x, y - 1) => ('_', &[(0, 1)]),
+                2 => ('-', &[(0, 1)]),
+                _ => ('\\', &[(-1, 1), (-1, 0), (0, 1)]),
+            };
+            // Only the strongest pixel across the edge is drawn as edge,
+            // so edges are one character thick. On a step both pixels next to it are
+            // equally strong, the later one is drawn
+            let is_edge = m > EDGE_THRESHOLD
+                && across.iter().all(|&(dx, dy)| {
+                    m > magnitude(x + dx, y + dy) && m >= magnitude(x - dx, y - dy)
+                });
+            let ch = if is_edge {
+                ch
+            } else {
+                settings.char_set[shades[(y * w + x) as usize]]
+            };
+
+            cells.push(ascii_cell(ch, p, p, settings));
+        }
+    }
+    frame(img.width(), img.height(), cells)
+}
+
+// Horizontal and vertical brightness gradient of every pixel using the sobel operator,
+// row by row. Pixels past the border are treated as copies of the border pixels.
+fn sobel(img: &RgbImage) -> Vec<(f32, f32)> {
+    let (w, h) = (img.width() as i64, img.height() as i64);
+    let luma = |x: i64, y: i64| {
+        let p = img.get_pixel(x.clamp(0, w - 1) as u32, y.clamp(0, h - 1) as u32);
+        color::brightness(p.0.map(f32::from))
+    };
+
+    let mut gradients = Vec::with_capacity((w * h) as usize);
+    for y in 0..h {
+        for x in 0..w {
+            let gx = luma(x + 1, y - 1) + 2. * luma(x + 1, y) + luma(x + 1, y + 1)
+                - luma(x - 1, y - 1)
+                - 2. * luma(x - 1, y)
+                - luma(x - 1, y + 1);
+            let gy = luma(x - 1, y + 1) + 2. * luma(x, y + 1) + luma(x + 1, y + 1)
+                - luma(x - 1, y - 1)
+                - 2. * luma(x, y - 1)
+                - luma(x + 1, y - 1);
+            gradients.push((gx, gy));
+        }
+    }
+    gradients
+}
+
+// Draws every two pixels on top of each other as one `▀` character,
+// colored with the top pixel as foreground and the bottom pixel as background.
+// Without color the pixels are drawn as blocks of the brighter ones.
+fn half_blocks(img: &RgbImage, colors: &RgbImage, settings: &PaxciiSettings) -> AsciiFrame {
+    let rows = img.height().div_ceil(2);
+    let mut cells = Vec::with_capacity((img.width() * rows) as usize);
+    let lit = lit(img, settings.dither);
+    let w = img.width() as usize;
+
+    for y in (0..img.height()).step_by(2) {
+        for x in 0..img.width() {
+            let top = colors.get_pixel(x, y).0;
+            // Last row of an image with odd height has no bottom pixel
+            let bottom = (y + 1 < img.height()).then(|| colors.get_pixel(x, y + 1).0);
+
+            if settings.color == ColorDepth::None {
+                let (x, y) = (x as usize, y as usize);
+                let top = lit[y * w + x];
+                let bottom = bottom.is_some() && lit[(y + 1) * w + x];
+                let glyph = match (top, bottom) {
+                    (false, false) => ' ',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (true, true) => '█',
                 };
+                cells.push(AsciiCell {
+                    glyph,
+                    ..Default::default()
+                });
+            } else {
+                cells.push(AsciiCell {
+                    glyph: '▀',
+                    fg: Some(top),
+                    bg: bottom,
+                });
+            }
+        }
+    }
+    frame(img.width(), rows, cells)
+}
+
+// Bit of each dot in a braille character by its position in the 2x4 block, as [y][x]
+pub(crate) const BRAILLE_DOTS: [[u32; 2]; 4] =
+    [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+// Draws every 2x4 block of pixels as a braille character with a dot for every bright pixel
+fn braille(img: &RgbImage, colors: &RgbImage, settings: &PaxciiSettings) -> AsciiFrame {
+    let (columns, rows) = (img.width().div_ceil(2), img.height().div_ceil(4));
+    let mut cells = Vec::with_capacity((columns * rows) as usize);
+    let lit = lit(img, settings.dither);
 
-                ascii_img += &ascii_pixel;
-                row_index += 1;
+    for cy in 0..rows {
+        for cx in 0..columns {
+            let mut dots = 0;
+            // Sum of the colors of the pixels with a dot and of all pixels
+            let mut sum = [0u32; 3];
+            let (mut cell_sum, mut cell_count) = ([0u32; 3], 0);
+            for (dy, row) in BRAILLE_DOTS.iter().enumerate() {
+                for (dx, bit) in row.iter().enumerate() {
+                    let (x, y) = (cx * 2 + dx as u32, cy * 4 + dy as u32);
+                    // Pixels outside of image have no dot
+                    if x >= img.width() || y >= img.height() {
+                        continue;
+                    }
+                    let p = colors.get_pixel(x, y).0;
+                    for i in 0..3 {
+                        cell_sum[i] += p[i] as u32;
+                    }
+                    cell_count += 1;
+                    if lit[(y * img.width() + x) as usize] {
+                        dots |= bit;
+                        for i in 0..3 {
+                            sum[i] += p[i] as u32;
+                        }
+                    }
+                }
             }
+
+            // Braille patterns start at U+2800 with the dots as the lowest byte
+            let glyph = char::from_u32(0x2800 + dots).unwrap();
+            let average = cell_sum.map(|c| (c / cell_count) as u8);
+            let mut braille_cell = match dots.count_ones() {
+                0 => ascii_cell(glyph, average, average, settings),
+                count => ascii_cell(glyph, sum.map(|c| (c / count) as u8), average, settings),
+            };
+            // Without dots only the background can be seen
+            if dots == 0 && braille_cell.bg.is_none() {
+                braille_cell.fg = None;
+            }
+            cells.push(braille_cell);
         }
-        // Turns all ansi attributes off
-        ascii_img.push_str("\x1b[0m");
-        self.ascii = Some(ascii_img);
     }
+    frame(columns, rows, cells)
 }
 
-// Creates a string composed of an ansi escape sequence for color and two ascii characters,
-// who compose the ascii pixel
-// https://stackoverflow.com/questions/4842424/list-of-ansi-color-escape-sequences
-fn truecolor(rgb: [u8; 3], ascii_char: char) -> String {
-    format!(
-        "\x1b[38;2;{};{};{}m{}{}",
-        rgb[0], rgb[1], rgb[2], ascii_char, ascii_char
-    )
+// Number of pixels in a glyph
+const GLYPH_PIXELS: usize = (GLYPH_SIZE * GLYPH_SIZE) as usize;
+
+// Draws every 8x8 block of pixels as the character from `char_set` with the most similar shape.
+// Characters the built-in font doesn't have are never used.
+fn glyphs(img: &RgbImage, colors: &RgbImage, settings: &PaxciiSettings) -> AsciiFrame {
+    let columns = img.width().div_ceil(GLYPH_SIZE);
+    let rows = img.height().div_ceil(GLYPH_SIZE);
+    let mut cells = Vec::with_capacity((columns * rows) as usize);
+
+    // Bitmaps of the candidates with 1 for set pixels and 0 for unset pixels
+    let candidates: Vec<(char, [f32; GLYPH_PIXELS])> = settings
+        .char_set
+        .iter()
+        .filter_map(|&ch| {
+            let glyph = font::glyph(ch)?;
+            let mut bitmap = [0.; GLYPH_PIXELS];
+            for (i, px) in bitmap.iter_mut().enumerate() {
+                let (x, y) = (i as u32 % GLYPH_SIZE, i as u32 / GLYPH_SIZE);
+                if font::pixel(&glyph, x, y) {
+                    *px = 1.;
+                }
+            }
+            Some((ch, bitmap))
+        })
+        .collect();
+
+    for cy in 0..rows {
+        for cx in 0..columns {
+            // Brightness of the pixels from 0 to 1, pixels outside of the image are black
+            let mut cell = [0.; GLYPH_PIXELS];
+            let mut cell_colors = [[0u8; 3]; GLYPH_PIXELS];
+            for (i, px) in cell.iter_mut().enumerate() {
+                let x = cx * GLYPH_SIZE + i as u32 % GLYPH_SIZE;
+                let y = cy * GLYPH_SIZE + i as u32 / GLYPH_SIZE;
+                if x < img.width() && y < img.height() {
+                    cell_colors[i] = colors.get_pixel(x, y).0;
+                    *px = color::brightness(img.get_pixel(x, y).0.map(f32::from)) / 255.;
+                }
+            }
+
+            // One distance per candidate
+            let best = candidates
+                .iter()
+                .map(|candidate| {
+                    let distance = glyph_distance(&cell, &candidate.1, settings.glyph_metric);
+                    (candidate, distance)
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            let Some(((ch, bitmap), _)) = best else {
+                cells.push(AsciiCell::default());
+                continue;
+            };
+
+            // Sums of the colors of all pixels and of the pixels under the set pixels of the glyph
+            let (mut sum_all, mut sum_lit, mut lit) = ([0u32; 3], [0u32; 3], 0);
+            for (rgb, &px) in cell_colors.iter().zip(bitmap) {
+                for c in 0..3 {
+                    sum_all[c] += rgb[c] as u32;
+                    if px > 0. {
+                        sum_lit[c] += rgb[c] as u32;
+                    }
+                }
+                if px > 0. {
+                    lit += 1;
+                }
+            }
+            // Average color of the pixels under the set pixels of the glyph,
+            // or of all pixels for glyphs without set pixels
+            let average_all = sum_all.map(|c| (c / GLYPH_PIXELS as u32) as u8);
+            let average_lit = if lit == 0 {
+                average_all
+            } else {
+                sum_lit.map(|c| (c / lit) as u8)
+            };
+            cells.push(ascii_cell(*ch, average_lit, average_all, settings));
+        }
+    }
+    frame(columns, rows, cells)
+}
+
+// How different a glyph bitmap is from the brightness of the pixels, smaller is more similar
+fn glyph_distance(cell: &[f32], bitmap: &[f32], metric: GlyphMetric) -> f32 {
+    match metric {
+        GlyphMetric::Error => cell.iter().zip(bitmap).map(|(a, b)| (a - b).powi(2)).sum(),
+        GlyphMetric::Ssim => -ssim(cell, bitmap),
+    }
+}
+
+// Structural similarity of two equally sized blocks of values from 0 to 1, using the whole
+// block as one window. 1 means identical
+fn ssim(a: &[f32], b: &[f32]) -> f32 {
+    // Constants that keep the division stable for flat blocks
+    const C1: f32 = 0.01 * 0.01;
+    const C2: f32 = 0.03 * 0.03;
+
+    let n = a.len() as f32;
+    let mean_a = a.iter().sum::<f32>() / n;
+    let mean_b = b.iter().sum::<f32>() / n;
+    let (mut var_a, mut var_b, mut covar) = (0., 0., 0.);
+    for (a, b) in a.iter().zip(b) {
+        var_a += (a - mean_a).powi(2) / n;
+        var_b += (b - mean_b).powi(2) / n;
+        covar += (a - mean_a) * (b - mean_b) / n;
+    }
+
+    (2. * mean_a * mean_b + C1) * (2. * covar + C2)
+        / ((mean_a.powi(2) + mean_b.powi(2) + C1) * (var_a + var_b + C2))
+}
+
+// Colored cell of a character, with `fg` as the color of the character
+// and `average` as the color of all of the pixels it is drawn over
+fn ascii_cell(glyph: char, fg: [u8; 3], average: [u8; 3], settings: &PaxciiSettings) -> AsciiCell {
+    let (fg, bg) = match settings.background {
+        _ if settings.color == ColorDepth::None => (None, None),
+        Background::None => (Some(fg), None),
+        Background::Pixel => (Some(color::contrasting(average)), Some(average)),
+        Background::Darkened => (Some(fg), Some(color::darken(average, 0.35))),
+    };
+    AsciiCell { glyph, fg, bg }
+}
+
+fn frame(width: u32, height: u32, cells: Vec<AsciiCell>) -> AsciiFrame {
+    AsciiFrame {
+        width,
+        height,
+        cells,
+    }
 }
@@ -16,21 +16,40 @@
 //! The cli can also serve as an example.
 //!
 //! ## Features
-//! There are two features: webcam and audio.
+//! There are three features: webcam, audio and rayon.
 //!
 //! The webcam uses the [nokhwa](https://crates.io/crates/nokhwa) crate.\
-//! The audio uses the [rodio](https://crates.io/crates/rodio) crate.
+//! The audio uses the [rodio](https://crates.io/crates/rodio) crate.\
+//! The rayon feature uses the [rayon](https://crates.io/crates/rayon) crate
+//! for converting video frames in parallel.
 
+mod adjust;
 #[cfg(feature = "audio")]
 pub mod audio;
+pub mod color;
+mod controls;
+mod delta;
+mod dither;
+mod font;
+pub mod frame;
+mod gif;
+mod html;
 pub mod img;
+mod raster;
 pub mod settings;
+mod svg;
+#[cfg(unix)]
+pub mod term;
 pub mod video;
 #[cfg(feature = "webcam")]
 pub mod webcam;
 
+pub use frame::{AsciiCell, AsciiFrame};
 pub use img::AsciiImage;
-pub use settings::PaxciiSettings;
-pub use video::AsciiVideo;
+pub use settings::{
+    Adjustments, Background, ColorDepth, Dither, GifSettings, GlyphMetric, Levels, LineEnding,
+    PaxciiSettings, PlaybackSettings, RasterSettings, RenderMode, SvgSettings, TextSettings,
+};
+pub use video::{AsciiVideo, PlaybackStats};
 #[cfg(feature = "webcam")]
 pub use webcam::webcam;
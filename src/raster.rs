@@ -0,0 +1,46 @@
+use crate::color;
+use crate::font::{self, GLYPH_SIZE};
+use crate::frame::AsciiFrame;
+use crate::settings::{ColorDepth, RasterSettings};
+use image::{Rgb, RgbImage};
+
+/// Draws `frame` with the built-in font, every character filling a cell of the size
+/// in `settings`.\
+/// Characters the font doesn't have are drawn as `?`.
+pub(crate) fn rasterize(
+    frame: &AsciiFrame,
+    depth: ColorDepth,
+    settings: &RasterSettings,
+) -> RgbImage {
+    let (cell_w, cell_h) = (settings.cell_width.max(1), settings.cell_height.max(1));
+    let mut img = RgbImage::new(frame.width * cell_w, frame.height * cell_h);
+
+    for (cy, row) in frame.rows().enumerate() {
+        for (cx, cell) in row.iter().enumerate() {
+            let glyph = font::glyph(cell.glyph)
+                .or_else(|| font::glyph('?'))
+                .unwrap();
+            let fg = cell
+                .fg
+                .and_then(|fg| color::reduce(fg, depth))
+                .unwrap_or(settings.foreground);
+            let bg = cell
+                .bg
+                .and_then(|bg| color::reduce(bg, depth))
+                .unwrap_or(settings.background);
+
+            for y in 0..cell_h {
+                for x in 0..cell_w {
+                    // Closest pixel of the glyph, stretched to the cell
+                    let set = font::pixel(&glyph, x * GLYPH_SIZE / cell_w, y * GLYPH_SIZE / cell_h);
+                    img.put_pixel(
+                        cx as u32 * cell_w + x,
+                        cy as u32 * cell_h + y,
+                        Rgb(if set { fg } else { bg }),
+                    );
+                }
+            }
+        }
+    }
+    img
+}
@@ -1,3 +1,7 @@
+use crate::color::{DEFAULT_BACKGROUND, DEFAULT_FOREGROUND};
+use crate::font;
+use std::time::Duration;
+
 // Characters used to display ASCII output
 /// `[' ', ' ', '.', ':', '!', '+', '*', 'e', '$', '@', '8']`
 pub const CHARS_LIGHT: [char; 11] = [' ', ' ', '.', ':', '!', '+', '*', 'e', '$', '@', '8'];
@@ -6,17 +10,201 @@ pub const CHARS_MEDIUM: [char; 5] = ['.', '*', 'e', 's', '◍'];
 /// `['░', '▒', '▓', '█']`
 pub const CHARS_FILLED: [char; 4] = ['░', '▒', '▓', '█'];
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How many colors the ascii output can use.
+pub enum ColorDepth {
+    /// No color
+    None,
+    /// The 16 standard terminal colors
+    Ansi16,
+    /// The xterm 256 color palette
+    Ansi256,
+    /// 24-bit rgb color
+    TrueColor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How pixels are drawn with characters.
+pub enum RenderMode {
+    /// Every character is one pixel drawn as a character from `char_set`, picked by brightness.
+    Chars,
+    /// Every character is two pixels on top of each other, drawn as `▀`
+    /// with the top pixel as foreground color and the bottom pixel as background color.\
+    /// Has twice the vertical resolution of [`RenderMode::Chars`].
+    HalfBlock,
+    /// Every character is a block of 2x4 pixels drawn as a braille pattern,
+    /// with a dot for every bright pixel. Colored with the average color of the dots.\
+    /// Best for line art and monochrome images.
+    Braille,
+    /// Like [`RenderMode::Chars`], but pixels on edges are drawn as `|`, `/`, `\\`, `-` or `_`
+    /// following the direction of the edge, so shapes get outlines.
+    Structural,
+    /// Every character is compared with a block of 8x8 pixels and the character from `char_set`
+    /// whose shape matches the pixels best is drawn, see [`GlyphMetric`].\
+    /// Gives much sharper results than [`RenderMode::Chars`] for diagrams and faces,
+    /// best with a large `char_set` like [`PaxciiSettings::chars_ascii`].
+    Glyph,
+}
+
+impl RenderMode {
+    /// How many image pixels, horizontally and vertically, are drawn in one character.
+    pub fn scale(&self) -> (u32, u32) {
+        match self {
+            RenderMode::Chars | RenderMode::Structural => (1, 1),
+            RenderMode::HalfBlock => (1, 2),
+            RenderMode::Braille => (2, 4),
+            RenderMode::Glyph => (8, 8),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Whether characters also get a background color, which turns the ascii into
+/// a solid color image with the characters as texture on top.\
+/// Only used with color. [`RenderMode::HalfBlock`] always sets the background.
+pub enum Background {
+    /// Only the characters are colored
+    None,
+    /// The background is the color of the pixels and the characters are
+    /// a lighter or darker color that stands out from it
+    Pixel,
+    /// The characters are the color of the pixels and the background is
+    /// a darkened version of it
+    Darkened,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How brightness and colors are dithered when they are reduced to the few levels
+/// that can be drawn, the characters of `char_set`, braille dots or a color palette.\
+/// Dithering replaces the banding of smooth gradients with patterns.
+pub enum Dither {
+    /// Every value is rounded to the closest level
+    None,
+    /// Floyd–Steinberg error diffusion, smooth but noisy
+    FloydSteinberg,
+    /// Atkinson error diffusion, keeps more contrast than Floyd–Steinberg
+    Atkinson,
+    /// Ordered dithering with a 4x4 bayer matrix, a regular crosshatch pattern
+    /// that doesn't flicker in video
+    Bayer4,
+    /// Ordered dithering with an 8x8 bayer matrix, finer than [`Dither::Bayer4`]
+    Bayer8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How well a character matches the pixels it is drawn over in [`RenderMode::Glyph`].
+pub enum GlyphMetric {
+    /// Squared difference between the brightness of the pixels and the character's bitmap.
+    /// The character with the smallest error is used.
+    Error,
+    /// Structural similarity of the pixels and the character's bitmap.
+    /// Cares more about the shape than the brightness.
+    Ssim,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How the range of brightness in an image is stretched before converting.
+pub enum Levels {
+    /// Brightness is left as is
+    None,
+    /// The darkest pixels become black and the brightest white
+    Auto,
+    /// Histogram equalization, every brightness is used by about the same number of pixels.
+    /// Brings out detail in dark and flat images
+    Equalize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Adjustments applied to images, video frames and webcam frames after they are resized
+/// and before they are converted to ascii.\
+/// The default changes nothing.
+pub struct Adjustments {
+    /// Added to every color, from -1 (black) to 1 (white)
+    pub brightness: f32,
+    /// Multiplies the difference of colors from middle gray. 1 changes nothing
+    pub contrast: f32,
+    /// Gamma correction. Above 1 brightens dark colors, below 1 darkens them
+    pub gamma: f32,
+    /// Multiplies the difference of colors from gray. 0 is grayscale, 1 changes nothing
+    pub saturation: f32,
+    /// Inverts colors, for terminals with a light background
+    pub invert: bool,
+    /// Stretches the range of brightness before the other adjustments.\
+    /// In video every frame is stretched on its own
+    pub levels: Levels,
+}
+
+impl Default for Adjustments {
+    fn default() -> Adjustments {
+        Adjustments {
+            brightness: 0.,
+            contrast: 1.,
+            gamma: 1.,
+            saturation: 1.,
+            invert: false,
+            levels: Levels::None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 /// Settings used when converting image or video to ascii.
 pub struct PaxciiSettings {
-    pub color: bool,
+    pub color: ColorDepth,
+    pub background: Background,
     pub char_set: Vec<char>,
+    /// Output width in terminal columns
     pub width: u32,
+    /// Output height in terminal rows
     pub height: u32,
+    /// Height of a terminal character cell divided by its width.
+    /// Most terminal fonts have cells about twice as tall as they are wide.
+    pub cell_aspect: f32,
+    /// Shrink the output in one direction so the image isn't stretched,
+    /// see [`PaxciiSettings::grid_size`].
     pub keep_aspect_ratio: bool,
+    pub mode: RenderMode,
+    /// How brightness is dithered when picking characters, blocks and braille dots,
+    /// and how colors are dithered to the palette of 16 and 256 color depths.
+    pub dither: Dither,
+    /// How characters are matched with pixels in [`RenderMode::Glyph`].
+    pub glyph_metric: GlyphMetric,
+    pub adjust: Adjustments,
 }
 
 impl PaxciiSettings {
+    /// Default settings with `chars` as the character set, see [`PaxciiSettings::chars_custom`].
+    pub fn from_chars(chars: &str, sort: bool) -> anyhow::Result<PaxciiSettings> {
+        let mut settings = PaxciiSettings::default();
+        settings.chars_custom(chars, sort)?;
+        Ok(settings)
+    }
+    /// Columns and rows of characters an image of `image_size` pixels is drawn in.\
+    /// With `keep_aspect_ratio` this is the largest size that fits in `width` and `height`
+    /// and shows the image with its own aspect ratio on cells of `cell_aspect`.
+    /// Otherwise it's `width` and `height`.
+    pub fn grid_size(&self, image_size: (u32, u32)) -> (u32, u32) {
+        if !self.keep_aspect_ratio {
+            return (self.width, self.height);
+        }
+        // Ratios of the output to the image, in units of cell widths
+        let wratio = self.width as f32 / image_size.0 as f32;
+        let hratio = self.height as f32 * self.cell_aspect / image_size.1 as f32;
+        let ratio = wratio.min(hratio);
+
+        let w = (image_size.0 as f32 * ratio).round() as u32;
+        let h = (image_size.1 as f32 * ratio / self.cell_aspect).round() as u32;
+        (
+            w.clamp(1, self.width.max(1)),
+            h.clamp(1, self.height.max(1)),
+        )
+    }
+    /// Size in pixels that images and video frames are resized to before converting.\
+    /// This is `width` and `height` multiplied by the scale of the render mode.
+    pub fn sample_size(&self) -> (u32, u32) {
+        let (scale_w, scale_h) = self.mode.scale();
+        (self.width * scale_w, self.height * scale_h)
+    }
     /// Changes `char_set` to [`CHARS_LIGHT`]
     pub fn chars_light(&mut self) {
         self.char_set = Vec::from(CHARS_LIGHT)
@@ -29,16 +217,174 @@ impl PaxciiSettings {
     pub fn chars_filled(&mut self) {
         self.char_set = Vec::from(CHARS_FILLED)
     }
+    /// Changes `char_set` to every printable ascii character
+    pub fn chars_ascii(&mut self) {
+        self.char_set = (' '..='~').collect()
+    }
+    /// Changes `char_set` to the characters of `chars`, ordered from darkest to brightest.\
+    /// With `sort` the characters are ordered by how much of their space they cover
+    /// in the built-in font, so they can be given in any order.
+    ///
+    /// Fails if there are less than two characters, or when sorting if the built-in font
+    /// doesn't have one of the characters. `char_set` is left as is on failure.
+    pub fn chars_custom(&mut self, chars: &str, sort: bool) -> anyhow::Result<()> {
+        let mut char_set: Vec<char> = chars.chars().collect();
+        if char_set.len() < 2 {
+            anyhow::bail!(
+                "Character set needs at least two characters, got {}",
+                char_set.len()
+            );
+        }
+
+        if sort {
+            let mut coverage = Vec::with_capacity(char_set.len());
+            for ch in &char_set {
+                match font::glyph(*ch) {
+                    Some(glyph) => coverage.push((*ch, font::coverage(&glyph))),
+                    None => anyhow::bail!("Can't sort character '{ch}', the font doesn't have it"),
+                }
+            }
+            // Stable, so characters with the same coverage keep their order
+            coverage.sort_by_key(|&(_, coverage)| coverage);
+            char_set = coverage.into_iter().map(|(ch, _)| ch).collect();
+        }
+
+        self.char_set = char_set;
+        Ok(())
+    }
 }
 
 impl Default for PaxciiSettings {
     fn default() -> PaxciiSettings {
         PaxciiSettings {
-            color: true,
+            color: ColorDepth::TrueColor,
+            background: Background::None,
             char_set: Vec::from(CHARS_MEDIUM),
-            width: 30,
+            width: 60,
             height: 30,
+            cell_aspect: 2.,
             keep_aspect_ratio: true,
+            mode: RenderMode::Chars,
+            dither: Dither::None,
+            glyph_metric: GlyphMetric::Error,
+            adjust: Adjustments::default(),
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+/// Settings used when playing ascii video in terminal.
+pub struct PlaybackSettings {
+    /// Only redraw the characters that changed since the previous frame
+    /// instead of clearing the screen and printing every frame whole.
+    pub delta: bool,
+    /// How far behind the playback clock a frame can be and still be shown.\
+    /// Later frames are skipped. When playing with audio the clock is the audio position.
+    pub sync_tolerance: Duration,
+    /// Read key presses during playback.
+    /// Space pauses, left and right arrows seek 5 seconds, `+` and `-` change speed
+    /// and `q` quits.\
+    /// Puts the terminal in raw mode while playing.
+    pub controls: bool,
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> PlaybackSettings {
+        PlaybackSettings {
+            delta: false,
+            sync_tolerance: Duration::from_millis(40),
+            controls: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// What ends every line of text output.
+pub enum LineEnding {
+    /// `\n`, used on linux and macos
+    Lf,
+    /// `\r\n`, used on windows
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Settings used when writing ascii as plain text, without colors or escape sequences.
+pub struct TextSettings {
+    /// Remove whitespace at the end of every line
+    pub trim: bool,
+    pub line_ending: LineEnding,
+}
+
+impl Default for TextSettings {
+    fn default() -> TextSettings {
+        TextSettings {
+            trim: false,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Settings used when writing ascii as an svg image.
+pub struct SvgSettings {
+    /// Css font family of the characters, should be a monospace font
+    pub font_family: String,
+    /// Font size in svg user units, which are pixels when the svg isn't scaled.\
+    /// Characters are 0.6 times as wide and lines 1.2 times as tall as the font size.
+    pub font_size: f32,
+}
+
+impl Default for SvgSettings {
+    fn default() -> SvgSettings {
+        SvgSettings {
+            font_family: String::from("monospace"),
+            font_size: 14.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Settings used when drawing ascii as an image with the built-in 8x8 bitmap font.
+pub struct RasterSettings {
+    /// Width of a character in pixels
+    pub cell_width: u32,
+    /// Height of a character in pixels. The glyphs are stretched to fill the cells,
+    /// so this should be about `cell_width` times the cell aspect ratio
+    /// the ascii was made for, see [`PaxciiSettings::cell_aspect`].
+    pub cell_height: u32,
+    /// Color of characters without a foreground color, and of all characters without color
+    pub foreground: [u8; 3],
+    /// Color behind characters without a background color
+    pub background: [u8; 3],
+}
+
+impl Default for RasterSettings {
+    fn default() -> RasterSettings {
+        RasterSettings {
+            cell_width: 8,
+            cell_height: 16,
+            foreground: DEFAULT_FOREGROUND,
+            background: DEFAULT_BACKGROUND,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// Settings used when writing ascii video as an animated gif.
+pub struct GifSettings {
+    /// How many times the animation plays again after the first time.
+    /// None repeats it forever
+    pub repeat: Option<u16>,
+    /// Most colors every frame can use, up to 256.\
+    /// Frames with more colors are quantized to this many, None uses up to 256
+    pub palette_size: Option<u16>,
+}
@@ -0,0 +1,85 @@
+use crate::color::{DEFAULT_BACKGROUND, DEFAULT_FOREGROUND};
+use crate::frame::{self, AsciiFrame};
+use crate::html::{escape, hex};
+use crate::settings::{ColorDepth, SvgSettings};
+
+// Width of a character and height of a line, relative to the font size
+const CHAR_WIDTH: f32 = 0.6;
+const LINE_HEIGHT: f32 = 1.2;
+// Distance of the baseline from the top of a line, relative to the font size.
+// Centers the usual ascent of 0.8 and descent of 0.2 in the line
+const BASELINE: f32 = 0.9;
+
+/// Svg document showing `frame`, with a `<tspan>` for every run of characters
+/// with the same colors and a `<rect>` behind every run with a background color.
+pub(crate) fn document(frame: &AsciiFrame, depth: ColorDepth, settings: &SvgSettings) -> String {
+    let char_width = settings.font_size * CHAR_WIDTH;
+    let line_height = settings.font_size * LINE_HEIGHT;
+    let width = num(frame.width as f32 * char_width);
+    let height = num(frame.height as f32 * line_height);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+        viewBox=\"0 0 {width} {height}\">\n\
+        <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        hex(DEFAULT_BACKGROUND)
+    );
+    let rows: Vec<_> = frame.rows().map(|row| frame::runs(row, depth)).collect();
+
+    // Backgrounds go first so they are behind all of the text.
+    // Crisp edges keep anti-aliasing from leaving seams between them
+    svg.push_str("<g shape-rendering=\"crispEdges\">\n");
+    for (y, runs) in rows.iter().enumerate() {
+        for run in runs {
+            if let Some(bg) = run.bg {
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    num(run.start as f32 * char_width),
+                    num(y as f32 * line_height),
+                    num(run.len as f32 * char_width),
+                    num(line_height),
+                    hex(bg)
+                ));
+            }
+        }
+    }
+
+    svg.push_str("</g>\n");
+
+    svg.push_str(&format!(
+        "<g font-family=\"{}\" font-size=\"{}\" fill=\"{}\" xml:space=\"preserve\">\n",
+        escape(&settings.font_family),
+        settings.font_size,
+        hex(DEFAULT_FOREGROUND)
+    ));
+    for (y, runs) in rows.iter().enumerate() {
+        let baseline = y as f32 * line_height + settings.font_size * BASELINE;
+        svg.push_str(&format!("<text y=\"{}\">", num(baseline)));
+        for run in runs {
+            // Spaces only show their background
+            if run.text.chars().all(|ch| ch == ' ') {
+                continue;
+            }
+            // Every run is placed and stretched to its columns, so it lines up
+            // even if the font is a bit wider or narrower than expected
+            svg.push_str(&format!(
+                "<tspan x=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\"",
+                num(run.start as f32 * char_width),
+                num(run.len as f32 * char_width)
+            ));
+            if let Some(fg) = run.fg {
+                svg.push_str(&format!(" fill=\"{}\"", hex(fg)));
+            }
+            svg.push_str(&format!(">{}</tspan>", escape(&run.text)));
+        }
+        svg.push_str("</text>\n");
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+// Svg number with at most two decimals, without the rounding errors of f32
+fn num(value: f32) -> String {
+    let num = format!("{value:.2}");
+    num.trim_end_matches('0').trim_end_matches('.').to_string()
+}
@@ -0,0 +1,81 @@
+//! Information about the terminal the ascii is shown in.
+
+use crossterm::terminal;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+
+// How long to wait for the terminal to answer a query
+const QUERY_TIMEOUT_MS: i32 = 100;
+
+/// Height of a terminal character cell divided by its width,
+/// to use as `cell_aspect` in [`PaxciiSettings`](crate::PaxciiSettings).\
+/// Returns None if the terminal doesn't tell its cell size, see [`cell_size`].
+pub fn cell_aspect() -> Option<f32> {
+    let (width, height) = cell_size()?;
+    Some(height as f32 / width as f32)
+}
+
+/// Width and height of a terminal character cell in pixels.\
+/// Uses the pixel size of the window from the `TIOCGWINSZ` ioctl, or if the terminal
+/// doesn't fill it in, asks the terminal with the `CSI 16 t` escape sequence.
+/// Returns None if neither works, like when there is no terminal.
+pub fn cell_size() -> Option<(u16, u16)> {
+    if let Ok(size) = terminal::window_size() {
+        if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 {
+            return Some((size.width / size.columns, size.height / size.rows));
+        }
+    }
+    query_cell_size()
+}
+
+// Asks the terminal for its cell size with `CSI 16 t`,
+// which terminals that support it answer with `CSI 6 ; height ; width t`
+fn query_cell_size() -> Option<(u16, u16)> {
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+
+    // In raw mode the answer can be read right away and isn't shown on screen
+    let was_raw = terminal::is_raw_mode_enabled().ok()?;
+    terminal::enable_raw_mode().ok()?;
+    let answer = read_answer(&mut tty);
+    if !was_raw {
+        terminal::disable_raw_mode().ok()?;
+    }
+
+    let answer = answer?;
+    let mut values = answer
+        .strip_prefix("\x1b[6;")?
+        .strip_suffix('t')?
+        .split(';');
+    let height = values.next()?.parse().ok()?;
+    let width = values.next()?.parse().ok()?;
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+// Sends the query and reads the answer up to its final `t`.
+// Gives up if the terminal doesn't answer in time
+fn read_answer(tty: &mut File) -> Option<String> {
+    tty.write_all(b"\x1b[16t").ok()?;
+    tty.flush().ok()?;
+
+    let mut answer = Vec::new();
+    let mut byte = [0];
+    while answer.last() != Some(&b't') && answer.len() < 32 {
+        let mut poll = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // Safety: `poll` is a valid pollfd and the count of 1 matches it
+        if unsafe { libc::poll(&mut poll, 1, QUERY_TIMEOUT_MS) } <= 0 {
+            return None;
+        }
+        tty.read_exact(&mut byte).ok()?;
+        answer.push(byte[0]);
+    }
+    String::from_utf8(answer).ok()
+}
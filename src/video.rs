@@ -1,16 +1,28 @@
+use crate::controls::{Control, Controls};
+use crate::delta::DeltaRenderer;
+use crate::frame::AsciiFrame;
+use crate::gif;
+use crate::html;
 use crate::img::AsciiImage;
-use crate::settings::PaxciiSettings;
+use crate::raster;
+use crate::settings::{ColorDepth, GifSettings, PaxciiSettings, PlaybackSettings, RasterSettings};
 use anyhow::{self, bail};
 use image::{DynamicImage, RgbImage};
-use std::fs;
-use std::io::{stdout, Write};
-use std::{process::Command, thread, time};
+use std::collections::hash_map::RandomState;
+use std::fs::{self, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, stdout, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::time::{Duration, Instant};
+use std::{fmt, thread};
 
 /// Struct holding the ascii video, output of `video_to_ascii`
 pub struct AsciiVideo {
     pub fps: f32,
     pub settings: PaxciiSettings,
-    pub ascii_frames: Option<Vec<String>>,
+    pub playback: PlaybackSettings,
+    pub ascii_frames: Option<Vec<AsciiFrame>>,
     pub raw_video: Option<Vec<u8>>,
     // mp3 encoded
     pub audio: Option<Vec<u8>>,
@@ -24,13 +36,14 @@ impl AsciiVideo {
     pub fn open(path: &str, settings: PaxciiSettings) -> anyhow::Result<Self> {
         let mut ascii_video = AsciiVideo::new(settings);
         ascii_video.ffmpeg(path)?;
-        ascii_video.video_to_ascii();
+        ascii_video.video_to_ascii()?;
         Ok(ascii_video)
     }
     pub fn new(settings: PaxciiSettings) -> Self {
         AsciiVideo {
             fps: 30.,
             settings,
+            playback: PlaybackSettings::default(),
             ascii_frames: None,
             raw_video: None,
             audio: None,
@@ -39,11 +52,10 @@ impl AsciiVideo {
     /// Transforms a whole video into ascii.\
     /// Takes a videos data in rgb bytes and returns the ascii frames.
     ///
-    /// Input video size must be same as values in [`PaxciiSettings`].
-    pub fn video_to_ascii(&mut self) {
+    /// Input video size must be same as `sample_size` in [`PaxciiSettings`].
+    pub fn video_to_ascii(&mut self) -> anyhow::Result<()> {
         if self.raw_video.is_none() {
-            eprintln!("`raw_video` is None. Can't make ascii frames");
-            return;
+            bail!("`raw_video` is None. Can't make ascii frames");
         }
         let raw_video = self.raw_video.as_ref().unwrap();
         let mut ascii_image = AsciiImage {
@@ -53,59 +65,51 @@ impl AsciiVideo {
         };
 
         // Byte size of one frame
-        let frame_size = self.settings.width * self.settings.height * 3;
+        let (width, height) = self.settings.sample_size();
+        let frame_size = width * height * 3;
 
         // Variable that will hold the frames of the ascii video
-        self.ascii_frames = Some(vec![String::with_capacity(
-            raw_video.len() / frame_size as usize,
-        )]);
+        self.ascii_frames = Some(Vec::with_capacity(raw_video.len() / frame_size as usize));
 
         // for frame in video
         for i in 0..(raw_video.len() as u32 / frame_size) {
             // Get bytes of one frame
             let frame =
                 raw_video[(i * frame_size) as usize..((i + 1) * frame_size) as usize].to_owned();
-            // Convert bytes to `DynamicImage`
-            ascii_image.image = Some(DynamicImage::ImageRgb8(
-                RgbImage::from_raw(self.settings.width, self.settings.height, frame).expect(
-                    "Error in `video_to_ascii` function when converting bytes to `DynamicImage`. \
-                Problem might lie in width and height values in `PaxciiSettings`.",
-                ),
-            ));
-
-            // Convert frame to ascii
-            ascii_image.image_to_ascii(false);
-            // Add ascii frame to video
+
+            // Convert frame to ascii and add it to video
             self.ascii_frames
                 .as_mut()
                 .unwrap()
-                .push(ascii_image.ascii.take().unwrap());
+                .push(frame_to_ascii(&mut ascii_image, frame)?);
         }
+        Ok(())
+    }
+    /// Same as `video_to_ascii` but converts frames on multiple threads.\
+    /// Frames stay in order and the output is the same as with `video_to_ascii`.
+    ///
+    /// `threads` is the number of threads to use, 0 uses one thread per cpu core.\
+    /// With the `rayon` feature the frames are converted on a rayon thread pool.
+    pub fn video_to_ascii_parallel(&mut self, threads: usize) -> anyhow::Result<()> {
+        if self.raw_video.is_none() {
+            bail!("`raw_video` is None. Can't make ascii frames");
+        }
+        let raw_video = self.raw_video.as_ref().unwrap();
+
+        self.ascii_frames = Some(convert_frames_parallel(
+            raw_video,
+            frame_size(&self.settings),
+            &self.settings,
+            threads,
+        )?);
+        Ok(())
     }
     /// Uses the ffmpeg and ffprobe command to split video into resized frames and
     /// change fps in [`PaxciiSettings`].\
     /// If `keep_aspect_ratio` is true then this function will also adjust width and height.\
     /// After `video_to_ascii` can be used to convert raw video to ascii frames.
     pub fn ffmpeg(&mut self, path: &str) -> anyhow::Result<()> {
-        let vsize = self.ffprobe(path)?;
-        (self.settings.width, self.settings.height) = if self.settings.keep_aspect_ratio {
-            keep_aspect_ratio(vsize, (self.settings.width, self.settings.height))
-        } else {
-            (self.settings.width, self.settings.height)
-        };
-
-        let cmd = Command::new("ffmpeg")
-            .args(["-i", &path])
-            .args([
-                "-vf",
-                &format!(
-                    "format=rgb24, scale={}:{}",
-                    self.settings.width, self.settings.height
-                ),
-            ])
-            .args(["-f", "rawvideo"])
-            .arg("-")
-            .output()?;
+        let cmd = self.ffmpeg_command(path)?.output()?;
 
         if cmd.status.success() {
             self.raw_video = Some(cmd.stdout);
@@ -114,6 +118,32 @@ impl AsciiVideo {
             bail!("ffmpeg stderr: {}", String::from_utf8_lossy(&cmd.stderr))
         }
     }
+    /// Starts ffmpeg and returns a [`FrameStream`] that reads and converts
+    /// one frame at a time from its output.\
+    /// Like `ffmpeg` this changes fps and, if `keep_aspect_ratio` is true, width and height
+    /// in [`PaxciiSettings`].\
+    /// Unlike `ffmpeg` the decoded video is never held in memory as a whole.
+    pub fn stream(&mut self, path: &str) -> anyhow::Result<FrameStream> {
+        let (child, stdout, stderr) = spawn_piped(self.ffmpeg_command(path)?)?;
+
+        Ok(FrameStream {
+            child,
+            stdout,
+            stderr,
+            ascii_image: AsciiImage::new(self.settings.clone()),
+            frame_size: frame_size(&self.settings),
+            path: path.to_string(),
+            fps: self.fps,
+        })
+    }
+    /// Builds the ffmpeg command that outputs the video as resized raw rgb frames.\
+    /// Runs `ffprobe` first so fps, width and height are already set when this returns.
+    fn ffmpeg_command(&mut self, path: &str) -> anyhow::Result<Command> {
+        let vsize = self.ffprobe(path)?;
+        (self.settings.width, self.settings.height) = self.settings.grid_size(vsize);
+
+        Ok(raw_video_command(path, &self.settings, Duration::ZERO))
+    }
 
     /// Sets fps using ffprobe command.\
     /// Returns video width and height.\
@@ -132,44 +162,194 @@ impl AsciiVideo {
             let w = vector[0].parse::<u32>()?;
             let h = vector[1].parse::<u32>()?;
 
+            // Kept exact, rounding 29.97 to 30 would drift from the audio
             let fps_division = vector[2].split("/").collect::<Vec<&str>>();
-            self.fps = (fps_division[0].parse::<f32>()? / fps_division[1].parse::<f32>()?).round();
+            let fps = fps_division[0].parse::<f64>()? / fps_division[1].parse::<f64>()?;
+            // Some streams report a frame rate of 0/0
+            if !(fps.is_finite() && fps > 0.) {
+                bail!("ffprobe reported invalid frame rate {}", vector[2]);
+            }
+            self.fps = fps as f32;
 
             Ok((w, h))
         } else {
             bail!("ffprobe stderr: {}", String::from_utf8_lossy(&cmd.stderr))
         }
     }
-    /// Print the ascii video to stdout
-    pub fn play(mut self) -> anyhow::Result<()> {
+    /// Print the ascii video to stdout.\
+    /// Frames are skipped when the terminal can't keep up with the video fps.
+    pub fn play(mut self) -> anyhow::Result<PlaybackStats> {
         if self.ascii_frames.is_none() {
             bail!("`ascii_frames` is empty. Can't play video in terminal.")
         }
+        let frames = BufferedFrames {
+            frames: self.ascii_frames.take().unwrap(),
+            index: 0,
+        };
+        self.play_frames(frames, &mut WallClock::new())
+    }
+    /// Print the video at `path` to stdout while it is being decoded.\
+    /// Frames are converted one by one as ffmpeg outputs them, so playback starts right away
+    /// and memory use doesn't grow with the length of the video.
+    pub fn play_stream(mut self, path: &str) -> anyhow::Result<PlaybackStats> {
+        let frames = self.stream(path)?;
+        self.play_frames(frames, &mut WallClock::new())
+    }
+    /// Prints frames to stdout at the video fps.\
+    /// Each frame is shown when `clock` reaches its time in the video.
+    /// Frames that are later than `sync_tolerance` in [`PlaybackSettings`] are skipped to catch up.
+    ///
+    /// If `controls` in [`PlaybackSettings`] is true, key presses pause, seek, change speed
+    /// and quit playback.
+    pub(crate) fn play_frames<F: Frames>(
+        &self,
+        mut frames: F,
+        clock: &mut dyn Clock,
+    ) -> anyhow::Result<PlaybackStats> {
+        if !(self.fps.is_finite() && self.fps > 0.) {
+            bail!("Can't play video with {} fps", self.fps);
+        }
+        // Puts terminal in raw mode until playback ends
+        let controls = if self.playback.controls {
+            Some(Controls::new()?)
+        } else {
+            None
+        };
         let mut lock = stdout().lock();
-        let fps = (1_000_000. / self.fps).round() as u64;
+        let mut delta = self.playback.delta.then(DeltaRenderer::default);
+        let mut stats = PlaybackStats::default();
 
-        // Format frames
-        let mut frames = Vec::with_capacity(self.ascii_frames.as_ref().unwrap().capacity());
-        for frame in self.ascii_frames.take().unwrap() {
-            frames.push(format!("\x1b[2J{frame}"));
-        }
+        let start = Instant::now();
+        // Time spent paused, which doesn't count as playback
+        let mut paused = Duration::ZERO;
+        // Index of the next frame in the video
+        let mut index = 0;
+        'play: loop {
+            let position = clock.position();
+            // Skip to the frame that should be on screen right now if the next one is too late
+            let skip =
+                if position.saturating_sub(self.frame_time(index)) > self.playback.sync_tolerance {
+                    self.frame_index(position).saturating_sub(index)
+                } else {
+                    0
+                };
+
+            // Skipped frames are never converted when streaming
+            let frame = match frames.nth(skip) {
+                Some(frame) => frame?,
+                None => break,
+            };
+            stats.frames_dropped += skip as u64;
+            index += skip;
 
-        for frame in frames {
-            let instant = time::Instant::now();
+            // Sleep until the clock reaches this frame, reacting to key presses meanwhile
+            let frame_time = self.frame_time(index);
+            loop {
+                let time = frame_time
+                    .saturating_sub(clock.position())
+                    .div_f32(clock.speed());
+                let control = match &controls {
+                    Some(controls) => controls.read(time)?,
+                    None => {
+                        thread::sleep(time);
+                        None
+                    }
+                };
+                if let Some(control) = control {
+                    let control_start = Instant::now();
+                    let pause = matches!(control, Control::Pause);
+                    let flow = self.control(control, &controls, &mut frames, clock, &mut index)?;
+                    if pause {
+                        paused += control_start.elapsed();
+                    }
+                    match flow {
+                        Flow::Continue => (),
+                        Flow::Seeked => continue 'play,
+                        Flow::Quit => break 'play,
+                    }
+                }
+                if clock.position() >= frame_time {
+                    break;
+                }
+            }
 
             // Write frame to stdout
-            lock.write_all(frame.as_bytes())
+            let mut out = match &mut delta {
+                Some(delta) => delta.render(&frame, self.settings.color),
+                None => format!("\x1b[2J{}", frame.to_ansi(self.settings.color)),
+            };
+            // Raw mode doesn't move the cursor to the start of the line on newline
+            if controls.is_some() {
+                out = out.replace('\n', "\r\n");
+            }
+            lock.write_all(out.as_bytes())
+                .and_then(|_| lock.flush())
                 .expect("Failed to write to stdout");
 
-            // Sleep between frames if time between two frames hasn't been longer than fps
-            if let Some(time) = fps.checked_sub(instant.elapsed().as_micros() as u64) {
-                thread::sleep(time::Duration::from_micros(time));
-            } else {
-                eprintln!("{} {}", self.fps, instant.elapsed().as_micros());
-                bail!("Terminal prints too slowly for video fps");
+            stats.frames_shown += 1;
+            index += 1;
+        }
+        stats.elapsed = start.elapsed().saturating_sub(paused);
+        Ok(stats)
+    }
+    // Applies a key press to playback.
+    // While paused this blocks until playback is resumed or quit.
+    fn control<F: Frames>(
+        &self,
+        control: Control,
+        controls: &Option<Controls>,
+        frames: &mut F,
+        clock: &mut dyn Clock,
+        index: &mut usize,
+    ) -> anyhow::Result<Flow> {
+        match control {
+            Control::Pause => {
+                clock.pause();
+                let mut flow = Flow::Continue;
+                // Keys only come from `controls` so it's always set here
+                while let Some(controls) = controls {
+                    match controls.read(Duration::from_secs(1))? {
+                        Some(Control::Pause) => break,
+                        Some(Control::Quit) => return Ok(Flow::Quit),
+                        Some(control) => {
+                            if let Flow::Seeked =
+                                self.control(control, &None, frames, clock, index)?
+                            {
+                                flow = Flow::Seeked;
+                            }
+                        }
+                        None => (),
+                    }
+                }
+                clock.resume();
+                Ok(flow)
+            }
+            Control::Seek(seconds) => {
+                let offset = Duration::from_secs(seconds.unsigned_abs());
+                let position = if seconds < 0 {
+                    clock.position().saturating_sub(offset)
+                } else {
+                    clock.position() + offset
+                };
+                *index = self.frame_index(position);
+                frames.seek(*index)?;
+                clock.seek(self.frame_time(*index))?;
+                Ok(Flow::Seeked)
+            }
+            Control::Speed(step) => {
+                clock.set_speed((clock.speed() + step).clamp(0.25, 4.));
+                Ok(Flow::Continue)
             }
+            Control::Quit => Ok(Flow::Quit),
         }
-        Ok(())
+    }
+    // Time of the frame with `index` since the start of the video
+    fn frame_time(&self, index: usize) -> Duration {
+        frame_time(index, self.fps)
+    }
+    // Index of the frame on screen at `position` since the start of the video
+    fn frame_index(&self, position: Duration) -> usize {
+        (position.as_secs_f64() * self.fps as f64) as usize
     }
     /// Write a bash script with the specified file name that plays the video in the terminal
     pub fn write_bash_script(&self, filename: &str) -> anyhow::Result<()> {
@@ -179,14 +359,13 @@ impl AsciiVideo {
 
         let ascii_frames = self.ascii_frames.as_ref().unwrap();
         // String that will hold the final script
-        // 24 is size of bash script added to each frame to print it, note this can vary a bit
-        let mut script = String::with_capacity(ascii_frames.len() * (ascii_frames[0].len() + 24));
+        let mut script = String::new();
 
         for frame in ascii_frames {
             // wrap frame in echo command, follow it by sleep command
             script.push_str(&format!(
                 "echo -e \"\x1b[2J{}\"\nsleep {}\n",
-                frame,
+                frame.to_ansi(self.settings.color),
                 1. / self.fps as f32
             ))
         }
@@ -195,17 +374,535 @@ impl AsciiVideo {
         fs::write(filename, script)?;
         Ok(())
     }
+    /// Write a self-contained html page with the specified file name that plays the video
+    /// in a browser at `fps`. Clicking the video pauses it.
+    pub fn write_html(&self, filename: &str) -> anyhow::Result<()> {
+        let Some(ascii_frames) = &self.ascii_frames else {
+            bail!("`ascii_frames` is empty. Can't write video to html.");
+        };
+        fs::write(
+            filename,
+            html::player(ascii_frames, self.settings.color, self.fps),
+        )?;
+        Ok(())
+    }
+    /// Write an animated gif with the specified file name that plays the video at `fps`.\
+    /// Frames are drawn with the built-in bitmap font, see [`AsciiFrame::to_image`].
+    pub fn write_gif(
+        &self,
+        filename: &str,
+        raster: &RasterSettings,
+        settings: &GifSettings,
+    ) -> anyhow::Result<()> {
+        let Some(ascii_frames) = &self.ascii_frames else {
+            bail!("`ascii_frames` is empty. Can't write video to gif.");
+        };
+        gif::write(
+            filename,
+            ascii_frames,
+            self.fps,
+            self.settings.color,
+            raster,
+            settings,
+        )
+    }
+    /// Encode the video with ffmpeg into a video file with the specified file name,
+    /// like mp4 or webm. ffmpeg picks the container and codecs from the file extension.\
+    /// Frames are drawn with the built-in bitmap font, see [`AsciiFrame::to_image`],
+    /// and play at `fps`. If `audio` is set, for example by `ffmpeg_audio`,
+    /// it is added to the video.
+    pub fn write_video(&self, filename: &str, raster: &RasterSettings) -> anyhow::Result<()> {
+        let Some(ascii_frames) = &self.ascii_frames else {
+            bail!("`ascii_frames` is empty. Can't write video to file.");
+        };
+        let Some(first) = ascii_frames.first() else {
+            bail!("`ascii_frames` has no frames. Can't write video to file.");
+        };
+        if ascii_frames
+            .iter()
+            .any(|frame| (frame.width, frame.height) != (first.width, first.height))
+        {
+            bail!("Frames have different sizes. Can't write video to file.");
+        }
+        let size = raster::rasterize(first, self.settings.color, raster).dimensions();
+
+        // ffmpeg can only read one input from stdin, so audio goes through a temporary file
+        let audio_path = match &self.audio {
+            Some(audio) => Some(write_temp(audio, "mp3")?),
+            None => None,
+        };
+
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-loglevel", "error", "-nostats", "-y"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .args(["-s", &format!("{}x{}", size.0, size.1)])
+            .args(["-r", &self.fps.to_string()])
+            .args(["-i", "-"]);
+        if let Some(audio_path) = &audio_path {
+            // Without -shortest the longer of video and audio sets the length
+            cmd.arg("-i")
+                .arg(audio_path)
+                .args(["-map", "0:v", "-map", "1:a"]);
+        }
+        // Most encoders need even sizes and players need yuv420p
+        cmd.args([
+            "-vf",
+            "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(filename);
+
+        let result = encode(cmd, ascii_frames, self.settings.color, raster);
+        if let Some(audio_path) = audio_path {
+            let _ = fs::remove_file(audio_path);
+        }
+        result
+    }
+}
+
+/// Time since the start of the video that playback follows.
+pub(crate) trait Clock {
+    fn position(&self) -> Duration;
+    fn speed(&self) -> f32;
+    fn set_speed(&mut self, speed: f32);
+    fn pause(&mut self);
+    fn resume(&mut self);
+    fn seek(&mut self, position: Duration) -> anyhow::Result<()>;
+}
+
+// Follows real time since playback started
+#[derive(Clone, Copy)]
+pub(crate) struct WallClock {
+    // Position at `since`
+    start: Duration,
+    // None while paused
+    since: Option<Instant>,
+    speed: f32,
+}
+
+impl WallClock {
+    pub(crate) fn new() -> WallClock {
+        WallClock::starting_at(Duration::ZERO)
+    }
+    pub(crate) fn starting_at(position: Duration) -> WallClock {
+        WallClock {
+            start: position,
+            since: Some(Instant::now()),
+            speed: 1.,
+        }
+    }
+}
+
+impl Clock for WallClock {
+    fn position(&self) -> Duration {
+        match self.since {
+            Some(since) => self.start + since.elapsed().mul_f32(self.speed),
+            None => self.start,
+        }
+    }
+    fn speed(&self) -> f32 {
+        self.speed
+    }
+    fn set_speed(&mut self, speed: f32) {
+        self.start = self.position();
+        self.since = self.since.map(|_| Instant::now());
+        self.speed = speed;
+    }
+    fn pause(&mut self) {
+        self.start = self.position();
+        self.since = None;
+    }
+    fn resume(&mut self) {
+        if self.since.is_none() {
+            self.since = Some(Instant::now());
+        }
+    }
+    fn seek(&mut self, position: Duration) -> anyhow::Result<()> {
+        self.start = position;
+        self.since = self.since.map(|_| Instant::now());
+        Ok(())
+    }
+}
+
+// What the play loop does after a key press
+enum Flow {
+    Continue,
+    // Frame index changed, the current frame is stale
+    Seeked,
+    Quit,
+}
+
+/// Frames that playback can seek in.
+pub(crate) trait Frames: Iterator<Item = anyhow::Result<AsciiFrame>> {
+    /// Makes the frame with `index` the next one returned.
+    fn seek(&mut self, index: usize) -> anyhow::Result<()>;
+}
+
+// Frames already converted to ascii
+pub(crate) struct BufferedFrames {
+    pub(crate) frames: Vec<AsciiFrame>,
+    // Index of next frame
+    pub(crate) index: usize,
+}
+
+impl Iterator for BufferedFrames {
+    type Item = anyhow::Result<AsciiFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.frames.get(self.index).cloned();
+        self.index += 1;
+        frame.map(Ok)
+    }
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.index += n;
+        self.next()
+    }
+}
+
+impl Frames for BufferedFrames {
+    fn seek(&mut self, index: usize) -> anyhow::Result<()> {
+        self.index = index;
+        Ok(())
+    }
+}
+
+/// Statistics of a finished playback. Returned by `play` and the other play functions.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PlaybackStats {
+    /// Number of frames written to the terminal
+    pub frames_shown: u64,
+    /// Number of frames skipped because the terminal couldn't keep up
+    pub frames_dropped: u64,
+    /// How long playback took, without the time it was paused
+    pub elapsed: Duration,
+}
+
+impl PlaybackStats {
+    /// Frames shown per second, 0 if no time has passed
+    pub fn fps(&self) -> f32 {
+        if self.elapsed.is_zero() {
+            return 0.;
+        }
+        self.frames_shown as f32 / self.elapsed.as_secs_f32()
+    }
+}
+
+impl fmt::Display for PlaybackStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} frames shown, {} frames dropped, {:.1} fps",
+            self.frames_shown,
+            self.frames_dropped,
+            self.fps()
+        )
+    }
+}
+
+// Time of the frame with `index` since the start of a video at `fps`.
+// Without a valid fps every frame is at the start instead of panicking
+fn frame_time(index: usize, fps: f32) -> Duration {
+    Duration::try_from_secs_f64(index as f64 / fps as f64).unwrap_or(Duration::ZERO)
+}
+
+// ffmpeg command that outputs the video at `path` as raw rgb frames
+// resized to the sample size of `settings`, starting from `start`
+fn raw_video_command(path: &str, settings: &PaxciiSettings, start: Duration) -> Command {
+    let (width, height) = settings.sample_size();
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-loglevel", "error"]);
+    if !start.is_zero() {
+        cmd.args(["-ss", &start.as_secs_f64().to_string()]);
+    }
+    cmd.args(["-i", path])
+        .args(["-vf", &format!("format=rgb24, scale={width}:{height}")])
+        .args(["-f", "rawvideo"])
+        .arg("-");
+    cmd
+}
+
+// Runs ffmpeg `cmd` with the frames drawn by `raster` written to its input as raw rgb
+fn encode(
+    mut cmd: Command,
+    frames: &[AsciiFrame],
+    depth: ColorDepth,
+    raster: &RasterSettings,
+) -> anyhow::Result<()> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stderr = Stderr::read(&mut child);
+
+    let mut stdin = child.stdin.take().unwrap();
+    for frame in frames {
+        // ffmpeg closes its input when it fails, the error is in its stderr
+        if stdin
+            .write_all(raster::rasterize(frame, depth, raster).as_raw())
+            .is_err()
+        {
+            break;
+        }
+    }
+    // Closing the input tells ffmpeg there are no more frames
+    drop(stdin);
+
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        bail!("ffmpeg stderr: {}", stderr.finish())
+    }
+}
+
+// Writes `contents` to a new file with a random name and extension `ext` in the temp directory.
+// The file must not exist yet, so a file or symlink put there by someone else is never written to
+fn write_temp(contents: &[u8], ext: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir();
+    for _ in 0..16 {
+        // Randomly seeded by std, no need for a rand dependency
+        let random = RandomState::new().build_hasher().finish();
+        let path = dir.join(format!("paxcii-{}-{random:016x}.{ext}", std::process::id()));
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if let Err(err) = file.write_all(contents) {
+            let _ = fs::remove_file(&path);
+            return Err(err.into());
+        }
+        return Ok(path);
+    }
+    bail!("Couldn't create a temporary file in {}", dir.display())
+}
+
+// Starts `cmd` with its output piped to us
+fn spawn_piped(mut cmd: Command) -> anyhow::Result<(Child, ChildStdout, Stderr)> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().unwrap();
+    let stderr = Stderr::read(&mut child);
+    Ok((child, stdout, stderr))
+}
+
+// Most bytes of stderr kept from a child process
+const STDERR_LIMIT: usize = 16 * 1024;
+
+// Stderr of a child process, read on its own thread so the child never blocks
+// on a full pipe while we only read its stdout. Only the last `STDERR_LIMIT` bytes are kept
+struct Stderr(Option<thread::JoinHandle<Vec<u8>>>);
+
+impl Stderr {
+    // Starts reading the piped stderr of `child`
+    fn read(child: &mut Child) -> Stderr {
+        let Some(mut pipe) = child.stderr.take() else {
+            return Stderr(None);
+        };
+        Stderr(Some(thread::spawn(move || {
+            let mut kept = Vec::new();
+            let mut buf = [0; 4096];
+            while let Ok(read) = pipe.read(&mut buf) {
+                if read == 0 {
+                    break;
+                }
+                kept.extend_from_slice(&buf[..read]);
+                if kept.len() > STDERR_LIMIT {
+                    kept.drain(..kept.len() - STDERR_LIMIT);
+                }
+            }
+            kept
+        })))
+    }
+    // Everything kept once the child has closed its stderr
+    fn finish(&mut self) -> String {
+        self.0
+            .take()
+            .and_then(|handle| handle.join().ok())
+            .map(|kept| String::from_utf8_lossy(&kept).into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Ascii frames read one at a time from a running ffmpeg process.\
+/// Returned by [`AsciiVideo::stream`]. Only one raw frame is held in memory at a time.
+pub struct FrameStream {
+    child: Child,
+    stdout: ChildStdout,
+    stderr: Stderr,
+    ascii_image: AsciiImage,
+    // Byte size of one frame
+    frame_size: usize,
+    // Needed to restart ffmpeg when seeking
+    path: String,
+    fps: f32,
+}
+
+impl FrameStream {
+    /// Reads the next raw frame from ffmpeg.\
+    /// Returns `None` once ffmpeg has no full frame left to output.
+    fn read_frame(&mut self) -> anyhow::Result<Option<Vec<u8>>> {
+        let mut frame = vec![0; self.frame_size];
+        let mut filled = 0;
+        while filled < self.frame_size {
+            let read = self.stdout.read(&mut frame[filled..])?;
+            if read == 0 {
+                self.finish()?;
+                return Ok(None);
+            }
+            filled += read;
+        }
+        Ok(Some(frame))
+    }
+    /// Waits for ffmpeg to exit and turns a failed exit into an error.
+    fn finish(&mut self) -> anyhow::Result<()> {
+        let status = self.child.wait()?;
+        if status.success() {
+            Ok(())
+        } else {
+            bail!("ffmpeg stderr: {}", self.stderr.finish())
+        }
+    }
+}
+
+impl Iterator for FrameStream {
+    type Item = anyhow::Result<AsciiFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_frame() {
+            Ok(Some(frame)) => Some(frame_to_ascii(&mut self.ascii_image, frame)),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+    // Skipped frames are read but not converted
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        for _ in 0..n {
+            match self.read_frame() {
+                Ok(Some(_)) => (),
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        self.next()
+    }
+}
+
+impl Frames for FrameStream {
+    // Restarts ffmpeg at the time of the frame
+    fn seek(&mut self, index: usize) -> anyhow::Result<()> {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+
+        let start = frame_time(index, self.fps);
+        (self.child, self.stdout, self.stderr) = spawn_piped(raw_video_command(
+            &self.path,
+            &self.ascii_image.settings,
+            start,
+        ))?;
+        Ok(())
+    }
+}
+
+impl Drop for FrameStream {
+    fn drop(&mut self) {
+        // Stop ffmpeg if the stream wasn't read to the end
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Converts all frames in `raw_video` on a rayon thread pool
+#[cfg(feature = "rayon")]
+fn convert_frames_parallel(
+    raw_video: &[u8],
+    frame_size: usize,
+    settings: &PaxciiSettings,
+    threads: usize,
+) -> anyhow::Result<Vec<AsciiFrame>> {
+    use rayon::prelude::*;
+
+    let convert = || {
+        raw_video
+            .par_chunks_exact(frame_size)
+            .map_init(
+                || AsciiImage::new(settings.clone()),
+                |ascii_image, frame| frame_to_ascii(ascii_image, frame.to_owned()),
+            )
+            .collect()
+    };
+
+    // 0 threads lets rayon decide
+    match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+        Ok(pool) => pool.install(convert),
+        Err(_) => convert(),
+    }
+}
+
+// Converts all frames in `raw_video` by splitting them into one chunk per thread
+#[cfg(not(feature = "rayon"))]
+fn convert_frames_parallel(
+    raw_video: &[u8],
+    frame_size: usize,
+    settings: &PaxciiSettings,
+    threads: usize,
+) -> anyhow::Result<Vec<AsciiFrame>> {
+    let threads = if threads == 0 {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    } else {
+        threads
+    };
+    let frame_count = raw_video.len() / frame_size;
+    // Number of frames each thread converts, rounded up
+    let chunk_frames = frame_count.div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let handles = raw_video[..frame_count * frame_size]
+            .chunks(chunk_frames * frame_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut ascii_image = AsciiImage::new(settings.clone());
+                    chunk
+                        .chunks_exact(frame_size)
+                        .map(|frame| frame_to_ascii(&mut ascii_image, frame.to_owned()))
+                        .collect::<anyhow::Result<Vec<AsciiFrame>>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Joining in spawn order keeps the frames in order
+        let mut ascii_frames = Vec::with_capacity(frame_count);
+        for handle in handles {
+            ascii_frames.extend(handle.join().expect("Frame conversion thread panicked")?);
+        }
+        Ok(ascii_frames)
+    })
+}
+
+// Byte size of one raw frame
+fn frame_size(settings: &PaxciiSettings) -> usize {
+    let (width, height) = settings.sample_size();
+    (width * height * 3) as usize
 }
 
-/// Resizes to keep aspect ratio. Returns one `new_size` with one of the values modified.
-/// Larger value is resized. For example if width is smaller than height then height gets resized and width stays the same.
-/// `original_size` is the dimensions of the input video or image.
-fn keep_aspect_ratio(original_size: (u32, u32), new_size: (u32, u32)) -> (u32, u32) {
-    let wratio = new_size.0 as f32 / original_size.0 as f32;
-    let hratio = new_size.1 as f32 / original_size.1 as f32;
-    let ratio = wratio.min(hratio);
+/// Converts the bytes of one raw rgb frame to ascii.\
+/// Frame size must be same as the sample size of the settings of `ascii_image`.
+fn frame_to_ascii(ascii_image: &mut AsciiImage, frame: Vec<u8>) -> anyhow::Result<AsciiFrame> {
+    let (width, height) = ascii_image.settings.sample_size();
+    // Convert bytes to `DynamicImage`
+    ascii_image.image = Some(DynamicImage::ImageRgb8(
+        RgbImage::from_raw(width, height, frame).expect(
+            "Error when converting frame bytes to `DynamicImage`. \
+            Problem might lie in width and height values in `PaxciiSettings`.",
+        ),
+    ));
 
-    let w = (original_size.0 as f32 * ratio).round() as u32;
-    let h = (original_size.1 as f32 * ratio).round() as u32;
-    (w, h)
+    // Convert frame to ascii
+    ascii_image.image_to_ascii(false)?;
+    Ok(ascii_image.ascii.take().unwrap())
 }
@@ -31,10 +31,14 @@ pub fn webcam(camera_index: u32, settings: &PaxciiSettings) -> anyhow::Result<()
                 let frame = DynamicImage::from(frame.decode_image::<RgbFormat>()?);
 
                 ascii_image.image = Some(frame);
-                ascii_image.image_to_ascii(true);
+                ascii_image.image_to_ascii(true)?;
 
                 // Write frame to stdout
-                write!(lock, "\x1b[2J{}", ascii_image.ascii.take().unwrap())?;
+                write!(
+                    lock,
+                    "\x1b[2J{}",
+                    ascii_image.ascii.take().unwrap().to_ansi(settings.color)
+                )?;
             }
             Err(err) => {
                 bail!("webcam error: {err}");
@@ -0,0 +1,427 @@
+use image::{DynamicImage, Rgb, RgbImage};
+use paxcii::*;
+
+// Gradient so every render mode draws something in every cell
+fn gradient(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+        let v = ((x + y) * 255 / (width + height)) as u8;
+        Rgb([v, 255 - v, v / 2])
+    }))
+}
+
+// Converts `image` as is, without resizing
+fn convert(image: DynamicImage, settings: PaxciiSettings) -> AsciiFrame {
+    let mut ascii_image = AsciiImage::new(settings);
+    ascii_image.image = Some(image);
+    ascii_image.image_to_ascii(false).unwrap();
+    ascii_image.ascii.unwrap()
+}
+
+// Visible characters of every line, with ansi escape sequences removed
+fn lines(ascii: &str) -> Vec<String> {
+    let mut visible = String::new();
+    let mut chars = ascii.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            // Escape sequences end with a letter
+            for ch in chars.by_ref() {
+                if ch.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            visible.push(ch);
+        }
+    }
+    visible.lines().map(String::from).collect()
+}
+
+// Asserts the frame has `width` x `height` cells and so does its terminal output
+fn assert_size(frame: &AsciiFrame, width: usize, height: usize) {
+    assert_eq!(
+        (frame.width as usize, frame.height as usize),
+        (width, height)
+    );
+    assert_eq!(frame.cells.len(), width * height);
+
+    let ascii = frame.to_ansi(ColorDepth::TrueColor);
+    let lines = lines(&ascii);
+    assert_eq!(lines.len(), height, "rows of {ascii:?}");
+    for line in lines {
+        assert_eq!(line.chars().count(), width, "columns of {line:?}");
+    }
+}
+
+#[test]
+fn chars_keeps_every_column() {
+    for color in [ColorDepth::None, ColorDepth::Ansi16, ColorDepth::TrueColor] {
+        let settings = PaxciiSettings {
+            color,
+            ..Default::default()
+        };
+        assert_size(&convert(gradient(5, 3), settings), 5, 3);
+    }
+}
+
+#[test]
+fn chars_single_pixel() {
+    assert_size(&convert(gradient(1, 1), PaxciiSettings::default()), 1, 1);
+}
+
+#[test]
+fn chars_draws_last_column() {
+    // Black image with a white last column
+    let image = RgbImage::from_fn(
+        4,
+        2,
+        |x, _| if x == 3 { Rgb([255; 3]) } else { Rgb([0; 3]) },
+    );
+    let settings = PaxciiSettings {
+        color: ColorDepth::None,
+        char_set: vec![' ', '#'],
+        ..Default::default()
+    };
+    let frame = convert(DynamicImage::ImageRgb8(image), settings);
+    assert_eq!(frame.to_plain(&TextSettings::default()), "   #\n   #\n");
+}
+
+#[test]
+fn structural_keeps_every_column() {
+    let settings = PaxciiSettings {
+        mode: RenderMode::Structural,
+        ..Default::default()
+    };
+    assert_size(&convert(gradient(7, 4), settings), 7, 4);
+}
+
+#[test]
+fn half_blocks_draw_two_rows_per_line() {
+    let settings = PaxciiSettings {
+        mode: RenderMode::HalfBlock,
+        ..Default::default()
+    };
+    assert_size(&convert(gradient(6, 4), settings.clone()), 6, 2);
+    // The last line of an odd height has only a top pixel
+    assert_size(&convert(gradient(6, 5), settings), 6, 3);
+}
+
+#[test]
+fn braille_draws_2x4_pixels_per_character() {
+    let settings = PaxciiSettings {
+        mode: RenderMode::Braille,
+        ..Default::default()
+    };
+    assert_size(&convert(gradient(4, 8), settings.clone()), 2, 2);
+    assert_size(&convert(gradient(5, 9), settings), 3, 3);
+}
+
+#[test]
+fn resize_fills_requested_size() {
+    for mode in [
+        RenderMode::Chars,
+        RenderMode::HalfBlock,
+        RenderMode::Braille,
+        RenderMode::Structural,
+        RenderMode::Glyph,
+    ] {
+        let settings = PaxciiSettings {
+            mode,
+            width: 13,
+            height: 7,
+            keep_aspect_ratio: false,
+            ..Default::default()
+        };
+        let mut ascii_image = AsciiImage::new(settings);
+        ascii_image.image = Some(gradient(100, 40));
+        ascii_image.image_to_ascii(true).unwrap();
+        assert_size(&ascii_image.ascii.unwrap(), 13, 7);
+    }
+}
+
+#[test]
+fn resize_keeps_aspect_ratio_on_tall_cells() {
+    // A square image on cells twice as tall as wide is twice as many columns as rows
+    let settings = PaxciiSettings {
+        width: 40,
+        height: 10,
+        cell_aspect: 2.,
+        ..Default::default()
+    };
+    let mut ascii_image = AsciiImage::new(settings);
+    ascii_image.image = Some(gradient(50, 50));
+    ascii_image.image_to_ascii(true).unwrap();
+    assert_size(&ascii_image.ascii.unwrap(), 20, 10);
+}
+
+#[test]
+fn frame_serializers_follow_edits() {
+    let mut frame = AsciiFrame::new(3, 2);
+    *frame.get_mut(1, 0).unwrap() = AsciiCell {
+        glyph: '#',
+        fg: Some([255, 0, 0]),
+        bg: Some([0, 0, 255]),
+    };
+    frame.get_mut(2, 1).unwrap().glyph = '@';
+    assert!(frame.get(3, 0).is_none());
+
+    assert_eq!(frame.to_plain(&TextSettings::default()), " # \n  @\n");
+    // Colors are reset where they end
+    assert_eq!(
+        frame.to_ansi(ColorDepth::TrueColor),
+        " \x1b[38;2;255;0;0m\x1b[48;2;0;0;255m#\x1b[0m \n  @\x1b[0m"
+    );
+    assert_eq!(frame.to_ansi(ColorDepth::None), " # \n  @");
+
+    // A foreground color at the end of a row doesn't color the next row
+    let mut frame = AsciiFrame::new(1, 2);
+    *frame.get_mut(0, 0).unwrap() = AsciiCell {
+        glyph: '#',
+        fg: Some([255, 0, 0]),
+        bg: None,
+    };
+    frame.get_mut(0, 1).unwrap().glyph = '@';
+    assert_eq!(
+        frame.to_ansi(ColorDepth::TrueColor),
+        "\x1b[38;2;255;0;0m#\x1b[0m\n@\x1b[0m"
+    );
+}
+
+#[test]
+fn plain_text_has_no_escape_sequences() {
+    let settings = PaxciiSettings {
+        char_set: vec![' ', '#'],
+        ..Default::default()
+    };
+    // Colored, with white pixels on the left and black on the right
+    let image = RgbImage::from_fn(4, 2, |x, _| if x < 2 { Rgb([255; 3]) } else { Rgb([0; 3]) });
+    let frame = convert(DynamicImage::ImageRgb8(image), settings);
+
+    assert_eq!(frame.to_plain(&TextSettings::default()), "##  \n##  \n");
+    let text = TextSettings {
+        trim: true,
+        line_ending: LineEnding::CrLf,
+    };
+    assert_eq!(frame.to_plain(&text), "##\r\n##\r\n");
+}
+
+#[test]
+fn html_merges_runs_of_same_color() {
+    let red = AsciiCell {
+        glyph: '<',
+        fg: Some([255, 0, 0]),
+        bg: None,
+    };
+    let mut frame = AsciiFrame::new(4, 1);
+    frame.cells = vec![red, red, AsciiCell::default(), red];
+
+    let html = frame.to_html(ColorDepth::TrueColor);
+    let content = &html[html.find('>').unwrap() + 1..html.rfind("</pre>").unwrap()];
+    assert_eq!(
+        content,
+        "<span style=\"color:#ff0000\">&lt;&lt;</span> <span style=\"color:#ff0000\">&lt;</span>"
+    );
+    // Without color there are no spans
+    assert!(!frame.to_html(ColorDepth::None).contains("<span"));
+}
+
+#[test]
+fn svg_places_runs_in_their_columns() {
+    let mut frame = AsciiFrame::new(4, 2);
+    frame.cells[1] = AsciiCell {
+        glyph: '&',
+        fg: Some([0, 255, 0]),
+        bg: Some([0, 0, 255]),
+    };
+    frame.cells[7].glyph = '#';
+    let settings = SvgSettings {
+        font_family: String::from("Fira Mono"),
+        font_size: 10.,
+    };
+
+    let svg = frame.to_svg(ColorDepth::TrueColor, &settings);
+    assert!(svg.contains("width=\"24\" height=\"24\""));
+    assert!(svg.contains("<rect x=\"6\" y=\"0\" width=\"6\" height=\"12\" fill=\"#0000ff\"/>"));
+    assert!(svg.contains("fill=\"#00ff00\">&amp;</tspan>"));
+    assert!(svg.contains(
+        "<tspan x=\"0\" textLength=\"24\" lengthAdjust=\"spacingAndGlyphs\">   #</tspan>"
+    ));
+    assert!(svg.contains("font-family=\"Fira Mono\""));
+}
+
+#[test]
+fn image_draws_cells_with_the_font() {
+    let mut frame = AsciiFrame::new(2, 1);
+    frame.cells[0] = AsciiCell {
+        glyph: '█',
+        fg: Some([255, 0, 0]),
+        bg: None,
+    };
+    frame.cells[1] = AsciiCell {
+        glyph: ' ',
+        fg: None,
+        bg: Some([0, 0, 255]),
+    };
+    let settings = RasterSettings {
+        cell_width: 4,
+        cell_height: 8,
+        foreground: [255; 3],
+        background: [0; 3],
+    };
+
+    let image = frame.to_image(ColorDepth::TrueColor, &settings);
+    assert_eq!(image.dimensions(), (8, 8));
+    assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0]);
+    assert_eq!(image.get_pixel(7, 7).0, [0, 0, 255]);
+    // Without color every character uses the default colors
+    let image = frame.to_image(ColorDepth::None, &settings);
+    assert_eq!(image.get_pixel(0, 0).0, [255; 3]);
+    assert_eq!(image.get_pixel(7, 7).0, [0; 3]);
+}
+
+#[test]
+fn chars_custom_needs_two_characters() {
+    let mut settings = PaxciiSettings::default();
+    assert!(settings.chars_custom("", false).is_err());
+    assert!(settings.chars_custom("#", true).is_err());
+    // Left as is on failure
+    assert_eq!(settings.char_set, PaxciiSettings::default().char_set);
+    assert!(settings.chars_custom("#.", false).is_ok());
+    assert_eq!(settings.char_set, ['#', '.']);
+}
+
+#[test]
+fn chars_custom_sorts_by_coverage() {
+    let mut settings = PaxciiSettings::default();
+    settings.chars_custom("#:. ", true).unwrap();
+    assert_eq!(settings.char_set, [' ', '.', ':', '#']);
+    // Without sorting the order is kept
+    settings.chars_custom("#:. ", false).unwrap();
+    assert_eq!(settings.char_set, ['#', ':', '.', ' ']);
+}
+
+#[test]
+fn short_char_set_is_an_error() {
+    let mut ascii_image = AsciiImage::new(PaxciiSettings {
+        char_set: Vec::new(),
+        ..Default::default()
+    });
+    ascii_image.image = Some(gradient(10, 10));
+    assert!(ascii_image.image_to_ascii(false).is_err());
+    assert!(ascii_image.ascii.is_none());
+
+    let mut ascii_video = AsciiVideo::new(ascii_image.settings);
+    ascii_video.raw_video = Some(vec![0; frame_bytes(&ascii_video.settings) * 2]);
+    assert!(ascii_video.video_to_ascii().is_err());
+    assert!(ascii_video.video_to_ascii_parallel(2).is_err());
+
+    for mode in [RenderMode::Chars, RenderMode::Structural, RenderMode::Glyph] {
+        let mut ascii_image = AsciiImage::new(PaxciiSettings {
+            mode,
+            char_set: vec!['#'],
+            ..Default::default()
+        });
+        ascii_image.image = Some(gradient(16, 16));
+        assert!(ascii_image.image_to_ascii(false).is_err(), "{mode:?}");
+    }
+    // Blocks and braille dots don't use the characters
+    for mode in [RenderMode::HalfBlock, RenderMode::Braille] {
+        let mut ascii_image = AsciiImage::new(PaxciiSettings {
+            mode,
+            char_set: Vec::new(),
+            ..Default::default()
+        });
+        ascii_image.image = Some(gradient(16, 16));
+        assert!(ascii_image.image_to_ascii(false).is_ok(), "{mode:?}");
+    }
+}
+
+// Byte size of one raw rgb frame with `settings`
+fn frame_bytes(settings: &PaxciiSettings) -> usize {
+    let (width, height) = settings.sample_size();
+    (width * height * 3) as usize
+}
+
+// Lines of the structural characters of a black and white image, white where `white` is true
+fn structural(width: u32, height: u32, white: impl Fn(u32, u32) -> bool) -> Vec<String> {
+    let image = DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+        if white(x, y) {
+            Rgb([255; 3])
+        } else {
+            Rgb([0; 3])
+        }
+    }));
+    let settings = PaxciiSettings {
+        mode: RenderMode::Structural,
+        color: ColorDepth::None,
+        char_set: vec![' ', '#'],
+        ..Default::default()
+    };
+    let frame = convert(image, settings);
+    lines(&frame.to_ansi(ColorDepth::None))
+}
+
+#[test]
+fn structural_edges_are_one_character_thick() {
+    // Edge characters of a line
+    let edges = |line: &String| {
+        line.chars()
+            .filter(|ch| !" #".contains(*ch))
+            .collect::<String>()
+    };
+
+    for line in structural(8, 8, |x, _| x >= 4) {
+        assert_eq!(line, "    |###");
+    }
+
+    let lines = structural(8, 8, |_, y| y >= 4);
+    let edge_rows: Vec<String> = lines.iter().map(edges).filter(|e| !e.is_empty()).collect();
+    assert_eq!(edge_rows.len(), 1, "{lines:?}");
+    assert!(edge_rows[0].len() == 8 && edge_rows[0].chars().all(|ch| ch == '-' || ch == '_'));
+
+    // The corners of diagonals touch the border of the image, which changes their direction
+    let lines = structural(12, 12, |x, y| x + y >= 12);
+    for line in &lines[1..11] {
+        assert_eq!(edges(line), "/", "{lines:?}");
+    }
+    let lines = structural(12, 12, |x, y| x >= y);
+    for line in &lines[1..11] {
+        assert_eq!(edges(line), "\\", "{lines:?}");
+    }
+}
+
+#[test]
+fn background_colors() {
+    let flat = || DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([200, 100, 50])));
+    let cells = |background, color| {
+        let settings = PaxciiSettings {
+            background,
+            color,
+            ..Default::default()
+        };
+        let frame = convert(flat(), settings);
+        assert_eq!(frame.cells.len(), 16);
+        frame
+            .cells
+            .iter()
+            .map(|cell| (cell.fg, cell.bg))
+            .collect::<Vec<_>>()
+    };
+
+    // Background is the pixel, the character a light tint of the dark pixel
+    for colors in cells(Background::Pixel, ColorDepth::TrueColor) {
+        assert_eq!(colors, (Some([235, 200, 183]), Some([200, 100, 50])));
+    }
+    // Character is the pixel, the background a darkened version of it
+    for colors in cells(Background::Darkened, ColorDepth::TrueColor) {
+        assert_eq!(colors, (Some([200, 100, 50]), Some([70, 35, 17])));
+    }
+    for colors in cells(Background::None, ColorDepth::TrueColor) {
+        assert_eq!(colors, (Some([200, 100, 50]), None));
+    }
+    // No colors at all without color
+    for background in [Background::None, Background::Pixel, Background::Darkened] {
+        for colors in cells(background, ColorDepth::None) {
+            assert_eq!(colors, (None, None));
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use image::codecs::gif::GifDecoder;
+use image::AnimationDecoder;
+use paxcii::*;
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Cursor;
+use std::path::PathBuf;
+
+// Video of `count` frames with a different color each
+fn video(count: usize, fps: f32) -> AsciiVideo {
+    let frames = (0..count)
+        .map(|i| {
+            let mut frame = AsciiFrame::new(3, 2);
+            for cell in &mut frame.cells {
+                cell.glyph = '#';
+                cell.fg = Some([(i * 60) as u8, 255, 0]);
+            }
+            frame
+        })
+        .collect();
+    let mut ascii_video = AsciiVideo::new(PaxciiSettings::default());
+    ascii_video.fps = fps;
+    ascii_video.ascii_frames = Some(frames);
+    ascii_video
+}
+
+// Path in the temp directory with a random name, so tests running at once don't share files
+fn temp_path(ext: &str) -> PathBuf {
+    let random = RandomState::new().build_hasher().finish();
+    std::env::temp_dir().join(format!(
+        "paxcii-test-{}-{random:016x}.{ext}",
+        std::process::id()
+    ))
+}
+
+#[test]
+fn gif_keeps_frame_rate() {
+    let path = temp_path("gif");
+    let path = path.to_str().unwrap();
+    let written =
+        video(4, 30.).write_gif(path, &RasterSettings::default(), &GifSettings::default());
+    let gif = fs::read(path);
+    let _ = fs::remove_file(path);
+    written.unwrap();
+
+    let frames = GifDecoder::new(Cursor::new(gif.unwrap()))
+        .unwrap()
+        .into_frames()
+        .collect_frames()
+        .unwrap();
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[0].buffer().dimensions(), (3 * 8, 2 * 16));
+    // Delays are in hundredths of a second, 30 fps is 3.33 of them per frame
+    let total: u32 = frames
+        .iter()
+        .map(|frame| {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            numer / denom
+        })
+        .sum();
+    assert_eq!(total, 130);
+}
+
+#[test]
+fn parallel_conversion_matches_sequential() {
+    let settings = PaxciiSettings {
+        width: 9,
+        height: 5,
+        ..Default::default()
+    };
+    let (width, height) = settings.sample_size();
+    // 7 frames, so they don't split evenly over 3 threads
+    let raw_video: Vec<u8> = (0..7u32)
+        .flat_map(|i| {
+            (0..width * height).flat_map(move |p| {
+                let v = ((p * 7 + i * 31) % 256) as u8;
+                [v, 255 - v, v / 2]
+            })
+        })
+        .collect();
+
+    let mut sequential = AsciiVideo::new(settings.clone());
+    sequential.raw_video = Some(raw_video.clone());
+    sequential.video_to_ascii().unwrap();
+    let expected = sequential.ascii_frames.unwrap();
+    assert_eq!(expected.len(), 7);
+
+    for threads in [0, 1, 3] {
+        let mut parallel = AsciiVideo::new(settings.clone());
+        parallel.raw_video = Some(raw_video.clone());
+        parallel.video_to_ascii_parallel(threads).unwrap();
+        assert_eq!(
+            parallel.ascii_frames.unwrap(),
+            expected,
+            "{threads} threads"
+        );
+    }
+}